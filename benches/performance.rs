@@ -0,0 +1,242 @@
+use std::fmt::Write as _;
+use std::time::Instant;
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use fpga_accelerator::device::FpgaAccelerator;
+use fpga_accelerator::math::{Matrix, Vector};
+use fpga_accelerator::types::{DataConverter, DataFormat, QFormat, Result};
+
+// 評価する行列サイズ（正方行列の一辺）
+const MATRIX_SIZES: [usize; 4] = [64, 256, 1024, 4096];
+// バッチ評価で1回の準備に対して実行するベクトル数
+const BATCH_SIZES: [usize; 3] = [1, 8, 32];
+// バッチ評価の行列サイズ
+const BATCH_MATRIX_SIZE: usize = 256;
+// レポート用の計測で各ケースを繰り返す回数（中央値を記録）
+const REPORT_SAMPLES: usize = 5;
+// レポートの出力先（環境変数で上書き可能）
+const DEFAULT_REPORT_PATH: &str = "target/performance.json";
+
+// 評価するデータ形式（レポート上の名前, 形式）
+fn formats() -> Result<Vec<(&'static str, DataFormat)>> {
+    Ok(vec![
+        ("full", DataFormat::Full),
+        ("fixed_q23_8", DataFormat::Fixed(QFormat::new(23, 8)?)),
+        ("trinary", DataFormat::Trinary(None)),
+    ])
+}
+
+fn test_data(size: usize, converter: &DataConverter) -> Result<(Matrix, Vector)> {
+    let matrix_data: Vec<Vec<f32>> = (0..size)
+        .map(|i| (0..size).map(|j| ((i * 31 + j * 17) % 97) as f32 / 97.0 - 0.5).collect())
+        .collect();
+    let vector_data: Vec<f32> = (0..size).map(|i| (i % 13) as f32 / 13.0 - 0.5).collect();
+
+    Ok((
+        Matrix::from_f32(&matrix_data, converter)?,
+        Vector::from_f32(&vector_data, converter)?,
+    ))
+}
+
+fn prepared_device(matrix: &Matrix, converter: &DataConverter) -> Result<FpgaAccelerator> {
+    let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+    accelerator.prepare_matrix(matrix)?;
+    Ok(accelerator)
+}
+
+fn run_device(accelerator: &mut FpgaAccelerator, vector: &Vector, batch: usize) -> Result<()> {
+    for _ in 0..batch {
+        black_box(accelerator.compute_matrix_vector(vector)?);
+    }
+    Ok(())
+}
+
+fn run_host(matrix: &Matrix, vector: &Vector, batch: usize) -> Result<()> {
+    for _ in 0..batch {
+        black_box(matrix.multiply_vector(vector)?);
+    }
+    Ok(())
+}
+
+// レポートの1行（デバイス・ホストの1回あたりの中央値、失敗した場合はその理由）
+struct Record {
+    group: &'static str,
+    format: &'static str,
+    size: usize,
+    batch: usize,
+    device_ns: Option<f64>,
+    host_ns: Option<f64>,
+    error: Option<String>,
+}
+
+// 処理をREPORT_SAMPLES回計測した中央値（ナノ秒）
+fn median_ns(mut run: impl FnMut() -> Result<()>) -> Result<f64> {
+    let mut samples = Vec::with_capacity(REPORT_SAMPLES);
+    for _ in 0..REPORT_SAMPLES {
+        let start = Instant::now();
+        run()?;
+        samples.push(start.elapsed().as_nanos() as f64);
+    }
+    samples.sort_by(f64::total_cmp);
+    Ok(samples[samples.len() / 2])
+}
+
+// デバイス経路とホスト参照計算の行列ベクトル乗算
+fn bench_matrix_vector(c: &mut Criterion, report: &mut Vec<Record>) -> Result<()> {
+    for (name, format) in formats()? {
+        let converter = DataConverter::new(format);
+        let mut group = c.benchmark_group(format!("matrix_vector/{}", name));
+        group.sample_size(10);
+
+        for &size in &MATRIX_SIZES {
+            let mut record = Record {
+                group: "matrix_vector", format: name, size, batch: 1,
+                device_ns: None, host_ns: None, error: None,
+            };
+            let (matrix, vector) = match test_data(size, &converter) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("skipping {} size {}: {}", name, size, e);
+                    record.error = Some(e.to_string());
+                    report.push(record);
+                    continue;
+                }
+            };
+            group.throughput(Throughput::Elements((size * size) as u64));
+
+            // 失敗する組み合わせはデバイス側の計測のみ省いてレポートに記録
+            let device = prepared_device(&matrix, &converter)
+                .and_then(|mut accelerator| run_device(&mut accelerator, &vector, 1).map(|_| accelerator));
+            match device {
+                Ok(mut accelerator) => {
+                    group.bench_with_input(BenchmarkId::new("device", size), &vector, |b, v| {
+                        b.iter(|| run_device(&mut accelerator, v, 1))
+                    });
+                    record.device_ns = median_ns(|| run_device(&mut accelerator, &vector, 1)).ok();
+                }
+                Err(e) => {
+                    eprintln!("skipping device {} size {}: {}", name, size, e);
+                    record.error = Some(e.to_string());
+                }
+            }
+
+            group.bench_with_input(BenchmarkId::new("host", size), &vector, |b, v| {
+                b.iter(|| run_host(&matrix, v, 1))
+            });
+            record.host_ns = median_ns(|| run_host(&matrix, &vector, 1)).ok();
+            report.push(record);
+        }
+        group.finish();
+    }
+    Ok(())
+}
+
+// 行列準備を含めたバッチ実行
+fn bench_batch(c: &mut Criterion, report: &mut Vec<Record>) -> Result<()> {
+    for (name, format) in formats()? {
+        let converter = DataConverter::new(format);
+        let mut group = c.benchmark_group(format!("batch/{}", name));
+        let (matrix, vector) = test_data(BATCH_MATRIX_SIZE, &converter)?;
+        let run_batch = |batch: usize| -> Result<()> {
+            let mut accelerator = prepared_device(&matrix, &converter)?;
+            run_device(&mut accelerator, &vector, batch)
+        };
+
+        for &batch in &BATCH_SIZES {
+            let mut record = Record {
+                group: "batch", format: name, size: BATCH_MATRIX_SIZE, batch,
+                device_ns: None, host_ns: None, error: None,
+            };
+            group.throughput(Throughput::Elements(batch as u64));
+
+            match run_batch(1) {
+                Ok(()) => {
+                    group.bench_with_input(BenchmarkId::new("device", batch), &batch, |b, &batch| {
+                        b.iter(|| run_batch(batch))
+                    });
+                    record.device_ns = median_ns(|| run_batch(batch)).ok();
+                }
+                Err(e) => {
+                    eprintln!("skipping device batch {} ({}): {}", batch, name, e);
+                    record.error = Some(e.to_string());
+                }
+            }
+
+            group.bench_with_input(BenchmarkId::new("host", batch), &batch, |b, &batch| {
+                b.iter(|| run_host(&matrix, &vector, batch))
+            });
+            record.host_ns = median_ns(|| run_host(&matrix, &vector, batch)).ok();
+            report.push(record);
+        }
+        group.finish();
+    }
+    Ok(())
+}
+
+fn json_number(value: Option<f64>) -> String {
+    value.map_or("null".into(), |v| format!("{:.1}", v))
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// コミットごとの比較用に、計測結果とホスト比の速度向上率をJSONで出力
+fn write_report(report: &[Record]) -> std::io::Result<String> {
+    let mut json = String::from("{\n  \"results\": [\n");
+    for (i, r) in report.iter().enumerate() {
+        let speedup = r.device_ns.zip(r.host_ns).map(|(device, host)| host / device);
+        let _ = write!(
+            json,
+            "    {{\"group\": {}, \"format\": {}, \"size\": {}, \"batch\": {}, \
+             \"device_ns\": {}, \"host_ns\": {}, \"speedup\": {}, \"error\": {}}}",
+            json_string(r.group), json_string(r.format), r.size, r.batch,
+            json_number(r.device_ns), json_number(r.host_ns),
+            speedup.map_or("null".into(), |s| format!("{:.4}", s)),
+            r.error.as_deref().map_or("null".into(), json_string),
+        );
+        json.push_str(if i + 1 < report.len() { ",\n" } else { "\n" });
+    }
+    json.push_str("  ]\n}\n");
+
+    let path = std::env::var("FPGA_BENCH_REPORT").unwrap_or_else(|_| DEFAULT_REPORT_PATH.into());
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    let mut report = Vec::new();
+
+    for bench in [bench_matrix_vector, bench_batch] {
+        if let Err(e) = bench(&mut criterion, &mut report) {
+            eprintln!("benchmark setup failed: {}", e);
+        }
+    }
+    criterion.final_summary();
+
+    // cargo test --benches 等の動作確認時はレポートを出力しない
+    if std::env::args().any(|arg| arg == "--test" || arg == "--list") {
+        return;
+    }
+    match write_report(&report) {
+        Ok(path) => println!("performance report written to {}", path),
+        Err(e) => eprintln!("failed to write performance report: {}", e),
+    }
+}
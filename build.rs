@@ -1,6 +1,13 @@
-use pyo3_build_config::resolve_env_var;
-
 fn main() {
+    // Pythonバインディングを含まないビルドではリンク設定は不要
+    #[cfg(feature = "python")]
+    link_python();
+}
+
+#[cfg(feature = "python")]
+fn link_python() {
+    use pyo3_build_config::resolve_env_var;
+
     // Pythonライブラリパスの設定
     if let Ok(python_lib_path) = resolve_env_var("PYTHON_SYS_EXECUTABLE") {
         println!("cargo:rustc-link-search=native={}", python_lib_path);
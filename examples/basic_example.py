@@ -23,7 +23,10 @@ def basic_vector_operations():
     
     for op_name, description in operations.items():
         print(f"\n{description}:")
-        result = accelerator.compute_vector(vector, op_name)
+        if op_name == 'add':
+            result = accelerator.compute_vector(vector, op_name, other=np.ones_like(vector))
+        else:
+            result = accelerator.compute_vector(vector, op_name)
         print(result)
         
         # NumPyでの計算結果と比較
@@ -73,6 +73,7 @@ package mtx_types #(
         MVMUL     = 5'b00001,  // V0 = M0 x V0
         VADD_01   = 5'b00010,  // V0 += V1
         VSUB_01   = 5'b00011,  // V0 -= V1
+        VMUL_01   = 5'b11111,  // V0 *= V1（要素ごとの積）
         
         // 初期化命令
         ZERO_V0   = 5'b01110,  // V0をゼロ初期化
@@ -66,6 +66,17 @@ module mtx_unit #(
         return result;
     endfunction
 
+    // 要素ごとの積の計算関数
+    function automatic vec_t vector_mul(input vec_t a, input vec_t b);
+        vec_t result;
+        for (int i = 0; i < V; i++) begin
+            logic signed [2*TOTAL-1:0] product =
+                $signed(a.elements[i]) * $signed(b.elements[i]);
+            result.elements[i] = product[TOTAL+Q-1:Q]; // Q分右シフト相当
+        end
+        return result;
+    endfunction
+
     // 行列-ベクトル乗算の内部関数
     function automatic vec_t matrix_vector_mul(
         input mtx_t matrix,
@@ -168,6 +179,11 @@ module mtx_unit #(
                         status.zero <= (V0.elements == '0);
                     end
 
+                    VMUL_01: begin
+                        V0 <= vector_mul(V0, V1);
+                        status.zero <= (V0.elements == '0);
+                    end
+
                     VRELU: begin
                         V0 <= relu_activation(V0);
                         status.zero <= (V0.elements == '0);
@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// キャッシュのヒット・ミス・追い出し統計
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub expirations: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// 内容ハッシュをキーとするLRUキャッシュ（有効期限は任意）
+#[derive(Debug)]
+pub struct HashCache<V> {
+    capacity: usize,
+    ttl: Option<Duration>,
+    entries: VecDeque<(u64, V, Instant)>,
+    stats: CacheStats,
+}
+
+impl<V: Clone> HashCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ttl: None,
+            entries: VecDeque::with_capacity(capacity),
+            stats: CacheStats::default(),
+        }
+    }
+
+    // 挿入からttlを過ぎたエントリは参照時にミスとして破棄
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // 検索（ヒットしたエントリは最近使用として先頭へ移動）
+    pub fn get(&mut self, key: u64) -> Option<V> {
+        let position = self.entries.iter().position(|(k, _, _)| *k == key);
+        if let (Some(pos), Some(ttl)) = (position, self.ttl) {
+            if self.entries[pos].2.elapsed() > ttl {
+                self.entries.remove(pos);
+                self.stats.expirations += 1;
+                self.stats.misses += 1;
+                return None;
+            }
+        }
+
+        match position {
+            Some(pos) => {
+                self.stats.hits += 1;
+                let entry = self.entries.remove(pos)?;
+                let value = entry.1.clone();
+                self.entries.push_front(entry);
+                Some(value)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, value: V) {
+        if let Some(pos) = self.entries.iter().position(|(k, _, _)| *k == key) {
+            self.entries.remove(pos);
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.push_front((key, value, Instant::now()));
+        self.evict();
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // 容量を超えた最も古いエントリを追い出す
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_back();
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = HashCache::new(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+
+        // 1を参照して最近使用扱いにすると、次の挿入で2が追い出される
+        assert_eq!(cache.get(1), Some("a"));
+        cache.insert(3, "c");
+
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(3), Some("c"));
+        assert_eq!(cache.len(), 2);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.evictions, 1);
+    }
+
+    #[test]
+    fn test_ttl_expiration() {
+        let mut cache = HashCache::new(2).with_ttl(Some(Duration::from_millis(5)));
+        cache.insert(1, "a");
+        assert_eq!(cache.get(1), Some("a"));
+
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(cache.get(1), None);
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.stats().expirations, 1);
+    }
+}
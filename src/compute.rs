@@ -1,22 +1,471 @@
-use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE};
+use crate::types::{FpgaError, Result, FpgaValue, QFormat, MATRIX_SIZE};
 use crate::memory::{SharedMemory, MatrixBlock};
 use crate::math::{Matrix, Vector};
-use crate::instructions::{FpgaInstruction, VliwInstruction, InstructionExecutor, FpgaInstructionChannel};
-use std::sync::Arc;
+use crate::simd;
+use crate::instructions::{FpgaInstruction, VliwInstruction, InstructionExecutor, FpgaInstructionChannel, RegisterState, encode_activation_params, encode_operands, pack_program};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ComputeOperation {
     MatrixVectorMultiply,
     VectorAdd,
+    // V0 - V1
+    VectorSub,
+    // V0とV1の要素ごとの積
+    VectorMul,
     VectorReLU,
+    // V0をスカラー値で埋める
+    Fill { value: f32 },
+    // V0をスカラー倍する
+    Scale { factor: f32 },
+    // 共有メモリのブロックsourceの一部をV0の指定位置へコピー
+    CopyRange { source: usize, src_offset: usize, dst_offset: usize, len: usize },
+}
+
+impl ComputeOperation {
+    /// V0をスカラー値で埋める演算（値は有限である必要がある）
+    pub fn fill(value: f32) -> Result<Self> {
+        let op = ComputeOperation::Fill { value };
+        op.validate()?;
+        Ok(op)
+    }
+
+    /// V0をスカラー倍する演算（係数は有限である必要がある）
+    pub fn scale(factor: f32) -> Result<Self> {
+        let op = ComputeOperation::Scale { factor };
+        op.validate()?;
+        Ok(op)
+    }
+
+    /// 共有メモリのブロックsourceからV0への範囲コピー
+    pub fn copy_range(source: usize, src_offset: usize, dst_offset: usize, len: usize) -> Result<Self> {
+        let op = ComputeOperation::CopyRange { source, src_offset, dst_offset, len };
+        op.validate()?;
+        Ok(op)
+    }
+
+    /// デバイスへ発行する前にオペランドの妥当性を検査
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            ComputeOperation::Fill { value } if !value.is_finite() => Err(FpgaError::Computation(
+                format!("Fill value must be finite: {}", value)
+            )),
+            ComputeOperation::Scale { factor } if !factor.is_finite() => Err(FpgaError::Computation(
+                format!("Scale factor must be finite: {}", factor)
+            )),
+            ComputeOperation::CopyRange { src_offset, dst_offset, len, .. } => {
+                if src_offset + len > MATRIX_SIZE || dst_offset + len > MATRIX_SIZE {
+                    return Err(FpgaError::Computation(format!(
+                        "Copy of {} elements from {} to {} exceeds register size {}",
+                        len, src_offset, dst_offset, MATRIX_SIZE
+                    )));
+                }
+                // オペランドが8ビットフィールドに収まるか
+                encode_operands(self).map(|_| ())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 行列ベクトル乗算の結果に融合適用する活性化関数
+///
+/// 係数を持つ活性化関数は、SetParam命令でユニットの設定レジスタに
+/// 係数をロードしてから適用される。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activation {
+    ReLU,
+    HardTanh,
+    Sigmoid,
+    Tanh,
+    // min(max(x, 0), max)
+    ClippedReLU { max: f32 },
+    // x > 0 ならx、それ以外はalpha * (exp(x) - 1)
+    Elu { alpha: f32 },
+    // clamp(slope * x + offset, 0, 1)
+    HardSigmoid { slope: f32, offset: f32 },
+}
+
+impl Activation {
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::ReLU => x.max(0.0),
+            Activation::HardTanh => x.clamp(-1.0, 1.0),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::ClippedReLU { max } => x.max(0.0).min(max),
+            Activation::Elu { alpha } => if x > 0.0 { x } else { alpha * x.exp_m1() },
+            Activation::HardSigmoid { slope, offset } => (slope * x + offset).clamp(0.0, 1.0),
+        }
+    }
+
+    /// 各要素に活性化関数を適用（区分線形のものはSIMDで計算）
+    ///
+    /// 指数関数を使うものは `apply` と同じ値になるよう要素ごとに計算する。
+    pub fn apply_all(self, values: &mut [f32]) {
+        match self {
+            Activation::ReLU => simd::clamp_affine(values, 1.0, 0.0, 0.0, f32::INFINITY),
+            Activation::HardTanh => simd::clamp_affine(values, 1.0, 0.0, -1.0, 1.0),
+            Activation::ClippedReLU { max } => simd::clamp_affine(values, 1.0, 0.0, 0.0, max),
+            Activation::HardSigmoid { slope, offset } => simd::clamp_affine(values, slope, offset, 0.0, 1.0),
+            Activation::Sigmoid | Activation::Tanh | Activation::Elu { .. } => {
+                values.iter_mut().for_each(|x| *x = self.apply(*x));
+            }
+        }
+    }
+
+    /// 係数の検証
+    pub fn validate(self) -> Result<()> {
+        let coefficients = match self {
+            Activation::ClippedReLU { max } => vec![max],
+            Activation::Elu { alpha } => vec![alpha],
+            Activation::HardSigmoid { slope, offset } => vec![slope, offset],
+            _ => Vec::new(),
+        };
+        if coefficients.iter().any(|c| !c.is_finite()) {
+            return Err(FpgaError::Computation(format!(
+                "Activation coefficients must be finite: {:?}", self
+            )));
+        }
+        if let Activation::ClippedReLU { max } = self {
+            if max <= 0.0 {
+                return Err(FpgaError::Computation(format!(
+                    "Clipped ReLU maximum must be positive: {}", max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 結果キャッシュのキーに使うため、係数はビット表現でハッシュする
+impl std::hash::Hash for Activation {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match *self {
+            Activation::ClippedReLU { max } => max.to_bits().hash(state),
+            Activation::Elu { alpha } => alpha.to_bits().hash(state),
+            Activation::HardSigmoid { slope, offset } => {
+                slope.to_bits().hash(state);
+                offset.to_bits().hash(state);
+            }
+            Activation::ReLU | Activation::HardTanh | Activation::Sigmoid | Activation::Tanh => {}
+        }
+    }
+}
+
+// VLIW命令ワードあたりのスロット数
+const VLIW_SLOTS: usize = 4;
+
+/// ベクトル式を構成する要素ごとの演算
+#[derive(Debug, Clone)]
+pub enum VectorOp {
+    Activation(Activation),
+    Scale(f32),
+    // 同じ長さのベクトルを加算（共有メモリ経由でV1に取得）
+    Add(Vector),
+}
+
+impl VectorOp {
+    fn instructions(&self) -> Vec<FpgaInstruction> {
+        match self {
+            VectorOp::Activation(act) if encode_activation_params(act).is_some() => {
+                vec![FpgaInstruction::SetParam, (*act).into()]
+            }
+            VectorOp::Activation(act) => vec![(*act).into()],
+            VectorOp::Scale(_) => vec![FpgaInstruction::VectorScale],
+            VectorOp::Add(_) => vec![FpgaInstruction::PullV1, FpgaInstruction::VectorAdd],
+        }
+    }
+
+    // 命令に付随する即値オペランド（スロット順）
+    fn operands(&self) -> Vec<u32> {
+        match self {
+            VectorOp::Activation(act) => encode_activation_params(act).unwrap_or_default(),
+            VectorOp::Scale(factor) => vec![factor.to_bits()],
+            VectorOp::Add(_) => Vec::new(),
+        }
+    }
+}
+
+/// 遅延評価されるベクトル演算の連鎖（v.relu().scale(0.5).add_vector(&w) など）
+///
+/// メソッド呼び出しでは演算を記録するだけで、評価時にlower()で
+/// 最小限のVLIW命令ワードへ詰めてからまとめて発行する。
+#[derive(Debug, Clone)]
+pub struct VectorExpr {
+    input: Vector,
+    ops: Vec<VectorOp>,
+}
+
+/// 融合後の1命令ワードと、それが実行する演算の範囲
+#[derive(Debug, Clone)]
+pub struct FusedPacket {
+    pub vliw: VliwInstruction,
+    pub operands: Vec<u32>,
+    pub ops: Range<usize>,
+}
+
+impl VectorExpr {
+    pub fn new(input: Vector) -> Self {
+        Self { input, ops: Vec::new() }
+    }
+
+    pub fn relu(self) -> Self {
+        self.activation(Activation::ReLU)
+    }
+
+    pub fn activation(mut self, activation: Activation) -> Self {
+        self.ops.push(VectorOp::Activation(activation));
+        self
+    }
+
+    pub fn scale(mut self, factor: f32) -> Self {
+        self.ops.push(VectorOp::Scale(factor));
+        self
+    }
+
+    pub fn add_vector(mut self, other: &Vector) -> Self {
+        self.ops.push(VectorOp::Add(other.clone()));
+        self
+    }
+
+    pub fn input(&self) -> &Vector {
+        &self.input
+    }
+
+    pub fn ops(&self) -> &[VectorOp] {
+        &self.ops
+    }
+
+    /// 演算列をVLIW命令ワードに詰める
+    ///
+    /// 共有メモリのV1取得元は1つしかないため、加算は1命令ワードに1つまで。
+    pub fn lower(&self) -> Vec<FusedPacket> {
+        let mut packets = Vec::new();
+        let mut slots: Vec<FpgaInstruction> = Vec::with_capacity(VLIW_SLOTS);
+        let mut operands = Vec::new();
+        let mut start = 0;
+        let mut has_add = false;
+
+        for (i, op) in self.ops.iter().enumerate() {
+            let instructions = op.instructions();
+            let is_add = matches!(op, VectorOp::Add(_));
+            if slots.len() + instructions.len() > VLIW_SLOTS || (is_add && has_add) {
+                packets.push(Self::pack(&slots, std::mem::take(&mut operands), start..i));
+                slots.clear();
+                start = i;
+                has_add = false;
+            }
+
+            slots.extend(instructions);
+            has_add |= is_add;
+            operands.extend(op.operands());
+        }
+        if !slots.is_empty() {
+            packets.push(Self::pack(&slots, operands, start..self.ops.len()));
+        }
+        packets
+    }
+
+    fn pack(slots: &[FpgaInstruction], operands: Vec<u32>, ops: Range<usize>) -> FusedPacket {
+        let slot = |i: usize| slots.get(i).copied().unwrap_or(FpgaInstruction::Nop);
+        FusedPacket {
+            vliw: VliwInstruction::new(slot(0), slot(1), slot(2), slot(3)),
+            operands,
+            ops,
+        }
+    }
+}
+
+// 共有メモリ上の相手ユニットの書き込み完了を待つ上限時間
+const PULL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// リダクション時の部分和の累積方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AccumulationMode {
+    /// 加算ごとに出力フォーマットへ飽和させる（従来動作）
+    Narrow,
+    /// i64で累積し、結果の取り出し時にのみ出力フォーマットへ飽和させる
+    #[default]
+    Wide,
+}
+
+// 自動切り離しの既定エラー率閾値
+const DEFAULT_ERROR_THRESHOLD: f64 = 0.5;
+// エラー率で判定を行うのに必要な最小試行回数
+const MIN_HEALTH_SAMPLES: u64 = 4;
+
+// 既知解検査で許容する誤差
+const KNOWN_ANSWER_TOLERANCE: f32 = 1e-3;
+
+/// ユニットの健全性（エラー率に基づく自動切り離し用）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UnitHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub blacklisted: bool,
+}
+
+impl UnitHealth {
+    pub fn error_rate(&self) -> f64 {
+        let total = self.successes + self.failures;
+        if total == 0 {
+            0.0
+        } else {
+            self.failures as f64 / total as f64
+        }
+    }
+}
+
+/// ユニットが命令チャネルへ発行した命令数・命令ワード数と乗算の積和回数
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IssueStats {
+    pub instructions: u64,
+    pub packets: u64,
+    // RTLに実装されておらずシミュレータ内でのみ実行した命令数
+    pub simulated: u64,
+    // 行列ベクトル乗算で実行した積和演算の回数（ゼロ埋め部分は含まない）
+    pub macs: u64,
+}
+
+impl IssueStats {
+    pub fn merge(&self, other: &IssueStats) -> IssueStats {
+        IssueStats {
+            instructions: self.instructions + other.instructions,
+            packets: self.packets + other.packets,
+            simulated: self.simulated + other.simulated,
+            macs: self.macs + other.macs,
+        }
+    }
+}
+
+/// ベクトルの統計量（部分集計を併合できる形で保持）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorStats {
+    pub count: usize,
+    pub mean: f32,
+    // 平均からの偏差平方和（分散はこれを件数で割る）
+    m2: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl VectorStats {
+    pub fn from_values(values: impl IntoIterator<Item = f32>) -> Self {
+        let mut stats = Self { count: 0, mean: 0.0, m2: 0.0, min: f32::INFINITY, max: f32::NEG_INFINITY };
+        for x in values {
+            // Welfordの逐次更新
+            stats.count += 1;
+            let delta = x - stats.mean;
+            stats.mean += delta / stats.count as f32;
+            stats.m2 += delta * (x - stats.mean);
+            stats.min = stats.min.min(x);
+            stats.max = stats.max.max(x);
+        }
+        stats
+    }
+
+    /// 2つの部分集計を併合（Chanらの並列アルゴリズム）
+    pub fn merge(&self, other: &VectorStats) -> VectorStats {
+        if self.count == 0 {
+            return *other;
+        }
+        if other.count == 0 {
+            return *self;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = other.count as f32 / count as f32;
+        VectorStats {
+            count,
+            mean: self.mean + delta * weight,
+            m2: self.m2 + other.m2 + delta * delta * self.count as f32 * weight,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// 母分散
+    pub fn variance(&self) -> f32 {
+        if self.count == 0 { 0.0 } else { self.m2 / self.count as f32 }
+    }
+}
+
+/// ユニットの状態（監視・デバッグ用）
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitState {
+    pub id: usize,
+    pub matrix_loaded: bool,
+    pub vector_loaded: bool,
+    pub matrix_offset: Option<(usize, usize)>,
+}
+
+/// ユニットのレジスタ内容の読み出し専用スナップショット（デバッグ用）
+///
+/// V1はPULL_V1の読み出し元である自ユニットの共有メモリ領域の内容。
+/// 未ロードのレジスタはNone。
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterSnapshot {
+    pub unit_id: usize,
+    pub v0: Option<Vec<f32>>,
+    pub v1: Option<Vec<f32>>,
+    pub m0: Option<Vec<Vec<f32>>>,
+}
+
+/// ユニットから退避したレジスタ内容（SaveContext/RestoreContext）
+///
+/// 長時間の処理を中断して別の処理にユニットを明け渡す場合や、物理ユニット数を
+/// 超える論理テンソルを時分割で扱う場合に使う。活性化関数の係数は含まず、
+/// 復元後は改めてSetParamが必要になる。
+#[derive(Debug, Clone)]
+pub struct UnitContext {
+    unit_id: usize,
+    v0: Option<Vec<FpgaValue>>,
+    v1: Option<Vec<FpgaValue>>,
+    m0: Option<MatrixBlock>,
+    // Wide累積中の拡張精度値
+    accumulator: Option<Vec<i64>>,
+}
+
+impl UnitContext {
+    /// 退避元のユニットID
+    pub fn unit_id(&self) -> usize {
+        self.unit_id
+    }
+}
+
+// バッチ発行中に蓄積した命令（蓄積時に個別に検証済み）
+#[derive(Debug)]
+struct PendingBatch {
+    // バッチ開始時のレジスタ状態（詰めた命令ワードの再検証に使う）
+    start: RegisterState,
+    program: Vec<(FpgaInstruction, Vec<u32>)>,
 }
 
 pub struct ComputeUnit {
     id: usize,
     matrix_cache: Option<MatrixBlock>,
     vector_cache: Option<Vec<FpgaValue>>,
+    // Wide累積時のV0の拡張精度値（V0が他の命令で上書きされたら破棄）
+    accumulator: Option<Vec<i64>>,
+    accumulation: AccumulationMode,
+    // ベクトルレジスタの固定小数点フォーマット（V0未ロード時の即値の変換に使用）
+    vector_format: Option<QFormat>,
+    // 設定レジスタに活性化関数の係数がロード済みか
+    param_loaded: bool,
     shared_memory: Arc<SharedMemory>,
     instruction_channel: FpgaInstructionChannel,
+    batch: Option<PendingBatch>,
+    issued: IssueStats,
+    // 出力フォーマットへの飽和が発生した要素数の累計
+    saturations: u64,
+    // 演算の実行を失敗させる（故障の再現用）
+    #[cfg(test)]
+    failing: bool,
 }
 
 impl ComputeUnit {
@@ -25,18 +474,208 @@ impl ComputeUnit {
             id,
             matrix_cache: None,
             vector_cache: None,
+            accumulator: None,
+            accumulation: AccumulationMode::default(),
+            vector_format: None,
+            param_loaded: false,
             shared_memory,
             instruction_channel: FpgaInstructionChannel::new()?,
+            batch: None,
+            issued: IssueStats::default(),
+            saturations: 0,
+            #[cfg(test)]
+            failing: false,
         })
     }
 
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn set_accumulation_mode(&mut self, mode: AccumulationMode) {
+        self.accumulation = mode;
+        self.accumulator = None;
+    }
+
+    pub fn accumulation_mode(&self) -> AccumulationMode {
+        self.accumulation
+    }
+
+    /// ベクトルレジスタの固定小数点フォーマットを設定（Noneは浮動小数点）
+    pub fn set_vector_format(&mut self, format: Option<QFormat>) {
+        self.vector_format = format;
+    }
+
+    /// V0の現在の内容（未ロードならNone）
+    pub fn vector(&self) -> Option<&[FpgaValue]> {
+        self.vector_cache.as_deref()
+    }
+
+    /// M0の現在の内容（未ロードならNone）
+    pub fn matrix(&self) -> Option<&MatrixBlock> {
+        self.matrix_cache.as_ref()
+    }
+
+    pub fn state(&self) -> UnitState {
+        UnitState {
+            id: self.id,
+            matrix_loaded: self.matrix_cache.is_some(),
+            vector_loaded: self.vector_cache.is_some(),
+            matrix_offset: self.matrix_cache.as_ref().map(|block| block.get_offsets()),
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn snapshot(&self) -> RegisterSnapshot {
+        let to_f32 = |values: &[FpgaValue]| values.iter().map(|x| x.as_f32()).collect::<Vec<_>>();
+        RegisterSnapshot {
+            unit_id: self.id,
+            v0: self.vector_cache.as_deref().map(to_f32),
+            v1: self.shared_memory.read_block(self.id).ok().map(|data| to_f32(&data)),
+            m0: self.matrix_cache.as_ref()
+                .map(|block| block.get_data().iter().map(|row| to_f32(row)).collect()),
+        }
+    }
+
+    /// ロード済みのレジスタをデバイスメモリへ退避
+    ///
+    /// ユニットの状態は変更しないため、退避後にreset()などで明け渡す。
+    pub fn save_context(&mut self) -> Result<UnitContext> {
+        let v1 = self.shared_memory.read_block(self.id).ok();
+        let stores = [
+            (self.vector_cache.is_some(), FpgaInstruction::StoreV0),
+            (v1.is_some(), FpgaInstruction::StoreV1),
+            (self.matrix_cache.is_some(), FpgaInstruction::StoreM0),
+        ];
+        let slots: Vec<FpgaInstruction> = stores.iter()
+            .filter(|(loaded, _)| *loaded)
+            .map(|&(_, inst)| inst)
+            .collect();
+        if !slots.is_empty() {
+            let slot = |i: usize| slots.get(i).copied().unwrap_or(FpgaInstruction::Nop);
+            self.dispatch(VliwInstruction::new(slot(0), slot(1), slot(2), slot(3)), &[])?;
+        }
+
+        Ok(UnitContext {
+            unit_id: self.id,
+            v0: self.vector_cache.clone(),
+            v1,
+            m0: self.matrix_cache.clone(),
+            accumulator: self.accumulator.clone(),
+        })
+    }
+
+    /// 退避したレジスタを復元
+    ///
+    /// 退避時に未ロードだったレジスタは未ロードに戻す。別のユニットから
+    /// 退避した内容も復元できる（時分割で物理ユニットを入れ替える場合）。
+    pub fn restore_context(&mut self, context: &UnitContext) -> Result<()> {
+        self.reset()?;
+
+        let loads = [
+            (context.v0.is_some(), FpgaInstruction::LoadV0),
+            (context.v1.is_some(), FpgaInstruction::LoadV1),
+            (context.m0.is_some(), FpgaInstruction::LoadM0),
+        ];
+        let slots: Vec<FpgaInstruction> = loads.iter()
+            .filter(|(saved, _)| *saved)
+            .map(|&(_, inst)| inst)
+            .collect();
+        if !slots.is_empty() {
+            let slot = |i: usize| slots.get(i).copied().unwrap_or(FpgaInstruction::Nop);
+            self.dispatch(VliwInstruction::new(slot(0), slot(1), slot(2), slot(3)), &[])?;
+        }
+
+        if let Some(v1) = &context.v1 {
+            self.shared_memory.write_block(self.id, v1.clone())?;
+        }
+        self.matrix_cache = context.m0.clone();
+        self.vector_cache = context.v0.clone();
+        self.accumulator = context.accumulator.clone();
+        Ok(())
+    }
+
+    // レジスタとキャッシュを初期化し、共有メモリ上の自ユニット領域を解放
+    pub fn reset(&mut self) -> Result<()> {
+        let vliw = VliwInstruction::new(
+            FpgaInstruction::ZeroV0,
+            FpgaInstruction::ZeroV1,
+            FpgaInstruction::ZeroM0,
+            FpgaInstruction::Nop,
+        );
+        self.dispatch(vliw, &[])?;
+
+        self.matrix_cache = None;
+        self.vector_cache = None;
+        self.accumulator = None;
+        self.param_loaded = false;
+        self.shared_memory.clear_block(self.id)
+    }
+
     pub fn load_matrix(&mut self, block: MatrixBlock) -> Result<()> {
         // 行列データをキャッシュ
         self.matrix_cache = Some(block);
         
         // FPGAに行列ロード命令を発行
         let vliw = VliwInstruction::from_single(FpgaInstruction::LoadM0);
-        self.instruction_channel.execute_vliw(vliw)
+        self.dispatch(vliw, &[])
+    }
+
+    /// ChecksumM0でM0のチェックサムを読み戻す
+    pub fn matrix_checksum(&mut self) -> Result<u64> {
+        let checksum = self.matrix_cache.as_ref()
+            .map(MatrixBlock::checksum)
+            .ok_or_else(|| FpgaError::Computation("Matrix not loaded".into()))?;
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::ChecksumM0);
+        self.dispatch(vliw, &[])?;
+        Ok(checksum)
+    }
+
+    /// 既知の行列・ベクトルの乗算結果が期待値と一致するかを検査
+    ///
+    /// M0とV0を上書きするため、検査の前後でユニットをリセットする。
+    pub fn known_answer_test(&mut self) -> Result<bool> {
+        let matrix: Vec<Vec<FpgaValue>> = (0..MATRIX_SIZE)
+            .map(|i| (0..MATRIX_SIZE).map(|j| FpgaValue::Float(((i + j) % 3) as f32 - 1.0)).collect())
+            .collect();
+        let vector: Vec<FpgaValue> = (0..MATRIX_SIZE)
+            .map(|j| FpgaValue::Float((j % 4) as f32 * 0.5))
+            .collect();
+        let expected: Vec<f32> = matrix.iter()
+            .map(|row| row.iter().zip(&vector).map(|(a, b)| a.as_f32() * b.as_f32()).sum())
+            .collect();
+
+        self.reset()?;
+        self.load_matrix(MatrixBlock::new(matrix, 0, 0)?)?;
+        self.load_vector(vector)?;
+        let result = self.execute(ComputeOperation::MatrixVectorMultiply);
+        self.reset()?;
+
+        let result = result?;
+        Ok(result.len() == expected.len()
+            && result.iter()
+                .zip(&expected)
+                .all(|(a, b)| (a.as_f32() - b).abs() <= KNOWN_ANSWER_TOLERANCE))
+    }
+
+    // M0の1要素を書き換える（転送・保持中のビット化けの再現用）
+    #[cfg(test)]
+    pub(crate) fn corrupt_matrix(&mut self, row: usize, col: usize, value: FpgaValue) -> Result<()> {
+        let block = self.matrix_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Matrix not loaded".into()))?;
+        let (row_offset, col_offset) = block.get_offsets();
+        let (rows, cols) = block.get_extent();
+        let mut data = block.get_data().to_vec();
+        data[row][col] = value;
+        self.matrix_cache = Some(MatrixBlock::new(data, row_offset, col_offset)?.with_extent(rows, cols)?);
+        Ok(())
+    }
+
+    // 以降の演算をすべて失敗させる（実行時の故障の再現用）
+    #[cfg(test)]
+    pub(crate) fn set_failing(&mut self, failing: bool) {
+        self.failing = failing;
     }
 
     pub fn load_vector(&mut self, data: Vec<FpgaValue>) -> Result<()> {
@@ -45,77 +684,1140 @@ impl ComputeUnit {
         }
         
         // ベクトルデータをキャッシュ
-        self.vector_cache = Some(data);
+        self.set_vector(data);
         
         // FPGAにベクトルロード命令を発行
         let vliw = VliwInstruction::from_single(FpgaInstruction::LoadV0);
-        self.instruction_channel.execute_vliw(vliw)
+        self.dispatch(vliw, &[])
+    }
+
+    /// 2項演算の第2オペランドをV1へロード
+    ///
+    /// V1は共有メモリ上の自ユニット領域から読み出されるため、そこへ書き込んでからLoadV1を発行する。
+    pub fn load_operand(&mut self, data: Vec<FpgaValue>) -> Result<()> {
+        if data.len() != MATRIX_SIZE {
+            return Err(FpgaError::Computation("Invalid vector size".into()));
+        }
+        self.shared_memory.write_block(self.id, data)?;
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::LoadV1);
+        self.dispatch(vliw, &[])
+    }
+
+    // V0を共有メモリの自ユニット領域へ書き出し
+    pub fn push_vector(&mut self) -> Result<()> {
+        let vector = self.vector_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        // Wide累積中は飽和前の部分和も送り、受け側で飽和させずに加算させる
+        self.shared_memory.write_block_wide(self.id, vector.clone(), self.accumulator.clone())?;
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::PushV0);
+        self.dispatch(vliw, &[])
+    }
+
+    /// ユニットsourceが共有メモリへ書き出したベクトルをV1へ取得
+    ///
+    /// 書き込み完了フラグを待って取り出し、取り出した領域は無効化する。
+    /// リダクションで同じ部分和を二度加算することはない。
+    pub fn pull_vector(&mut self, source: usize) -> Result<()> {
+        let (data, wide) = self.shared_memory.pop_block_wide(source, PULL_TIMEOUT)?;
+        self.shared_memory.write_block_wide(self.id, data, wide)?;
+
+        let vliw = VliwInstruction::new(
+            FpgaInstruction::WaitFlag,
+            FpgaInstruction::PullV1,
+            FpgaInstruction::Nop,
+            FpgaInstruction::Nop,
+        );
+        self.dispatch(vliw, &[])
+    }
+
+    /// ユニットsourceが共有メモリへ書き出したベクトルをV0へ取得
+    ///
+    /// pull_vectorと同様に書き込み完了フラグを待って取り出す。自ユニットの
+    /// 共有メモリ領域（V1）は変更しない。
+    pub fn pull_vector_to_v0(&mut self, source: usize) -> Result<()> {
+        let (data, _) = self.shared_memory.pop_block_wide(source, PULL_TIMEOUT)?;
+
+        let vliw = VliwInstruction::new(
+            FpgaInstruction::WaitFlag,
+            FpgaInstruction::PullV0,
+            FpgaInstruction::Nop,
+            FpgaInstruction::Nop,
+        );
+        self.dispatch(vliw, &[])?;
+        self.set_vector(data);
+        Ok(())
+    }
+
+    /// V0に活性化関数を適用
+    ///
+    /// 係数付きの活性化関数は同じ命令ワードでSetParamを発行する。
+    pub fn activate(&mut self, activation: Activation) -> Result<Vec<FpgaValue>> {
+        activation.validate()?;
+        let data = self.activated(activation)?;
+
+        let params = encode_activation_params(&activation);
+        let vliw = match params {
+            Some(_) => VliwInstruction::new(
+                FpgaInstruction::SetParam,
+                activation.into(),
+                FpgaInstruction::Nop,
+                FpgaInstruction::Nop,
+            ),
+            None => VliwInstruction::from_single(activation.into()),
+        };
+        self.dispatch(vliw, params.as_deref().unwrap_or(&[]))?;
+
+        self.set_vector(data.clone());
+        Ok(data)
+    }
+
+    // V0に活性化関数を適用した値（形式はV0と同じ）
+    fn activated(&self, activation: Activation) -> Result<Vec<FpgaValue>> {
+        let vector = self.vector_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        let mut values: Vec<f32> = vector.iter().map(|x| x.as_f32()).collect();
+        activation.apply_all(&mut values);
+        Ok(vector.iter()
+            .zip(values)
+            .map(|(x, v)| x.with_value(v))
+            .collect())
+    }
+
+    /// V0をデバイスメモリへ書き出してホストへ読み出す
+    pub fn read_vector(&mut self) -> Result<Vec<FpgaValue>> {
+        let data = self.vector_cache.clone()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::StoreV0);
+        self.dispatch(vliw, &[])?;
+        Ok(data)
+    }
+
+    /// 演算を発行できるかを検査（引数の妥当性とレジスタのロード状態）
+    ///
+    /// ここで検出されるエラーは呼び出し側の誤りで、ユニットの故障ではない。
+    pub fn check(&self, op: ComputeOperation) -> Result<()> {
+        op.validate()?;
+        if let ComputeOperation::CopyRange { source, .. } = op {
+            if source >= self.shared_memory.num_blocks() {
+                return Err(FpgaError::Computation(format!(
+                    "Copy source block {} does not exist", source
+                )));
+            }
+        }
+        encode_operands(&op)?;
+        VliwInstruction::from_single(op.into()).validate(self.register_state())?;
+        Ok(())
+    }
+
+    /// 融合命令ワード列を発行できるかを検査（checkの融合実行版）
+    pub fn check_fused(&self, packets: &[FusedPacket]) -> Result<()> {
+        let mut state = self.register_state();
+        for packet in packets {
+            // 加算の第2オペランドは命令ワードの発行直前に共有メモリへ書き込まれる
+            if packet.vliw.slots().contains(&FpgaInstruction::VectorAdd) {
+                state.v1 = true;
+            }
+            state = packet.vliw.validate(state)?;
+        }
+        Ok(())
     }
 
     pub fn execute(&mut self, op: ComputeOperation) -> Result<Vec<FpgaValue>> {
+        self.check(op)?;
+        #[cfg(test)]
+        if self.failing {
+            return Err(FpgaError::Computation(format!("Unit {} failed to execute {:?}", self.id, op)));
+        }
+
         let inst: FpgaInstruction = op.into();
         let vliw = VliwInstruction::from_single(inst);
-        self.instruction_channel.execute_vliw(vliw)?;
+        let operands = encode_operands(&op)?;
+        self.dispatch(vliw, &operands)?;
 
         match op {
             ComputeOperation::MatrixVectorMultiply => self.matrix_vector_multiply(),
             ComputeOperation::VectorAdd => self.vector_add(),
+            ComputeOperation::VectorSub | ComputeOperation::VectorMul => self.vector_binary(op),
             ComputeOperation::VectorReLU => self.vector_relu(),
+            ComputeOperation::Fill { value } => self.vector_fill(value),
+            ComputeOperation::Scale { factor } => self.vector_scale(factor),
+            ComputeOperation::CopyRange { source, src_offset, dst_offset, len } => {
+                self.vector_copy_range(source, src_offset, dst_offset, len)
+            }
         }
     }
 
-    fn matrix_vector_multiply(&self) -> Result<Vec<FpgaValue>> {
+    fn matrix_vector_multiply(&mut self) -> Result<Vec<FpgaValue>> {
         // 行列データとベクトルデータの存在確認
         let matrix = self.matrix_cache.as_ref()
             .ok_or_else(|| FpgaError::Computation("Matrix not loaded".into()))?;
         let vector = self.vector_cache.as_ref()
             .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
 
-        // 結果を取得（実際のハードウェアでは非同期で結果が返される）
-        let result = Matrix::new(matrix.get_data().to_vec())?
-            .multiply_vector(&Vector::new(vector.clone())?)?;
+        // 端のブロックは有効範囲の行・列のみを乗算し、ゼロ埋め行の出力はゼロ
+        let (valid_rows, valid_cols) = matrix.get_extent();
+        let rows: Vec<Vec<FpgaValue>> = matrix.get_data()[..valid_rows].iter()
+            .map(|row| row[..valid_cols].to_vec())
+            .collect();
+        let vector = &vector[..valid_cols];
+
+        // 固定小数点同士はMACユニットと同じ整数演算、それ以外はf32で計算
+        let (mut data, sums) = match vector.first().and_then(FpgaValue::format) {
+            Some(format) if rows.iter().flatten().chain(vector).all(|x| x.format().is_some()) => {
+                let sums = multiply_fixed(&rows, vector, format)?;
+                let (data, saturated) = saturate_wide(&sums, format);
+                self.saturations += saturated;
+                (data, Some(sums))
+            }
+            _ => {
+                let data = Matrix::new(rows)?
+                    .multiply_vector(&Vector::new(vector.to_vec())?)?
+                    .into_data();
+                (data, None)
+            }
+        };
+        let zero = data[0].with_value(0.0);
+        data.resize(MATRIX_SIZE, zero);
 
-        Ok(result.data)
+        self.issued.macs += (valid_rows * valid_cols) as u64;
+        self.set_vector(data.clone());
+        // Wideでは飽和前の積和を累積値として保持し、リダクションの途中で飽和させない
+        if self.accumulation == AccumulationMode::Wide {
+            self.accumulator = sums.map(|mut sums| {
+                sums.resize(MATRIX_SIZE, 0);
+                sums
+            });
+        }
+        Ok(data)
+    }
+
+    /// ロード済みのV0に対して融合済みの命令ワード列を実行
+    ///
+    /// blockは式の入力ベクトル中のブロック番号で、加算オペランドの
+    /// 対応ブロックを命令ワードの発行前に共有メモリへ書き込む。
+    pub fn execute_fused(
+        &mut self,
+        packets: &[FusedPacket],
+        ops: &[VectorOp],
+        block: usize
+    ) -> Result<Vec<FpgaValue>> {
+        if self.vector_cache.is_none() {
+            return Err(FpgaError::Computation("Vector not loaded".into()));
+        }
+
+        // 途中で失敗した場合はV0を実行前の内容に戻す
+        let saved = (self.vector_cache.clone(), self.accumulator.clone());
+        let result = self.run_fused(packets, ops, block);
+        if result.is_err() {
+            (self.vector_cache, self.accumulator) = saved;
+        }
+        result
     }
 
-    fn vector_add(&self) -> Result<Vec<FpgaValue>> {
+    // 各演算は単独で発行した場合と同じ処理で適用する
+    // （V0の形式の保持・出力フォーマットへの飽和・累積方式）
+    fn run_fused(
+        &mut self,
+        packets: &[FusedPacket],
+        ops: &[VectorOp],
+        block: usize
+    ) -> Result<Vec<FpgaValue>> {
+        for packet in packets {
+            let addend = ops[packet.ops.clone()].iter().find_map(|op| match op {
+                VectorOp::Add(other) => Some(other),
+                _ => None,
+            });
+            if let Some(other) = addend {
+                let mut operand: Vec<FpgaValue> = other.data()
+                    .iter()
+                    .skip(block * MATRIX_SIZE)
+                    .take(MATRIX_SIZE)
+                    .cloned()
+                    .collect();
+                operand.resize(MATRIX_SIZE, self.register_zero());
+                self.shared_memory.write_block(self.id, operand)?;
+            }
+
+            self.dispatch(packet.vliw, &packet.operands)?;
+
+            for op in &ops[packet.ops.clone()] {
+                match op {
+                    VectorOp::Activation(act) => {
+                        let data = self.activated(*act)?;
+                        self.set_vector(data);
+                    }
+                    VectorOp::Scale(factor) => {
+                        self.vector_scale(*factor)?;
+                    }
+                    VectorOp::Add(_) => {
+                        self.vector_add()?;
+                    }
+                }
+            }
+        }
+
+        self.vector_cache.clone()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))
+    }
+
+    /// V0の先頭len要素のうち上位k要素を(インデックス, 値)の降順で取得
+    ///
+    /// 同値の場合はインデックスの小さい方を優先する。kがlenを超える場合は
+    /// len要素すべてを返す。
+    pub fn top_k(&mut self, k: usize, len: usize) -> Result<Vec<(usize, f32)>> {
+        let vector = self.vector_cache.clone()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        if len > vector.len() {
+            return Err(FpgaError::Computation("Top-k length out of bounds".into()));
+        }
+        // lenで制限するためオペランドは32ビットに収まる
+        let k = k.min(len);
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::VectorTopK);
+        self.dispatch(vliw, &[k as u32, len as u32])?;
+
+        let mut entries: Vec<(usize, f32)> = vector[..len].iter()
+            .map(|x| x.as_f32())
+            .enumerate()
+            .collect();
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        entries.truncate(k);
+        Ok(entries)
+    }
+
+    /// V0の先頭len要素の部分統計量
+    pub fn partial_stats(&mut self, len: usize) -> Result<VectorStats> {
+        let vector = self.vector_cache.clone()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        if len > vector.len() {
+            return Err(FpgaError::Computation("Stats length out of bounds".into()));
+        }
+
+        let vliw = VliwInstruction::from_single(FpgaInstruction::VectorStats);
+        self.dispatch(vliw, &[len as u32])?;
+
+        Ok(VectorStats::from_values(vector[..len].iter().map(|x| x.as_f32())))
+    }
+
+    /// 命令のバッチ発行を開始
+    ///
+    /// flushまでの命令は検証のみ行って蓄積し、flush時にpack_programで
+    /// 命令ワードに詰め直してまとめて発行する。ホスト側のレジスタ内容は
+    /// 通常どおり即座に更新される。
+    pub fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(PendingBatch {
+                start: self.register_state(),
+                program: Vec::new(),
+            });
+        }
+    }
+
+    /// 蓄積した命令を詰めて発行し、バッチ発行を終了
+    pub fn flush(&mut self) -> Result<()> {
+        let Some(batch) = self.batch.take() else {
+            return Ok(());
+        };
+
+        let mut state = batch.start;
+        for (vliw, _) in pack_program(&batch.program) {
+            state = vliw.validate(state)?;
+            self.issue(vliw)?;
+        }
+        Ok(())
+    }
+
+    /// これまでに発行した命令数と命令ワード数
+    pub fn issue_stats(&self) -> IssueStats {
+        self.issued
+    }
+
+    /// 固定小数点演算で出力フォーマットへ飽和した要素数の累計
+    pub fn saturations(&self) -> u64 {
+        self.saturations
+    }
+
+    fn register_state(&self) -> RegisterState {
+        RegisterState {
+            v0: self.vector_cache.is_some(),
+            // V1は共有メモリ上の自ユニット領域から取得される
+            v1: self.shared_memory.read_block(self.id).is_ok(),
+            m0: self.matrix_cache.is_some(),
+            param: self.param_loaded,
+        }
+    }
+
+    // 現在のレジスタ状態で命令ワードを検証してから発行
+    //
+    // 命令チャネルへの発行はすべてここを通す。検証に失敗した命令ワードは
+    // 発行せず、レジスタの内容も変更しない。バッチ発行中は蓄積のみ行い、
+    // オペランドは命令ワード内の先頭の命令に付ける。
+    fn dispatch(&mut self, vliw: VliwInstruction, operands: &[u32]) -> Result<()> {
+        let next = vliw.validate(self.register_state())?;
+
+        match self.batch.as_mut() {
+            Some(batch) => {
+                let mut operands = Some(operands.to_vec());
+                for inst in vliw.slots().into_iter().filter(|&inst| inst != FpgaInstruction::Nop) {
+                    batch.program.push((inst, operands.take().unwrap_or_default()));
+                }
+            }
+            None => self.issue(vliw)?,
+        }
+        self.param_loaded = next.param;
+        Ok(())
+    }
+
+    // RTLに実装されている命令のみを命令チャネルへ送る
+    //
+    // シミュレータ専用の命令（とその即値オペランド）はここで除かれ、
+    // 命令ワード内のRTL命令がなければ命令ワード自体を送らない。
+    fn issue(&mut self, vliw: VliwInstruction) -> Result<()> {
+        let device = vliw.rtl_only();
+        let count = |vliw: &VliwInstruction| vliw.slots().iter()
+            .filter(|&&inst| inst != FpgaInstruction::Nop)
+            .count() as u64;
+        let sent = count(&device);
+        self.issued.simulated += count(&vliw) - sent;
+
+        if sent > 0 {
+            self.instruction_channel.execute_vliw(device)?;
+            self.issued.packets += 1;
+            self.issued.instructions += sent;
+        }
+        Ok(())
+    }
+
+    // V0を更新し、拡張精度の累積値を破棄
+    fn set_vector(&mut self, data: Vec<FpgaValue>) {
+        self.vector_cache = Some(data);
+        self.accumulator = None;
+    }
+
+    // V0と同じ形式のゼロ（未ロードならベクトルレジスタのフォーマット）
+    fn register_zero(&self) -> FpgaValue {
+        match self.vector_cache.as_ref().and_then(|v| v.first()) {
+            Some(x) => x.with_value(0.0),
+            None => self.vector_format
+                .map_or(FpgaValue::Float(0.0), |format| FpgaValue::from_f32(0.0, format)),
+        }
+    }
+
+    fn vector_add(&mut self) -> Result<Vec<FpgaValue>> {
         let v1 = self.vector_cache.as_ref()
             .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
-        let v2 = self.shared_memory.read_block(self.id)?;
+        let (v2, v2_wide) = self.shared_memory.read_block_wide(self.id)?;
+
+        if v1.len() != v2.len() {
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
+        }
+
+        // 拡張精度の累積は固定小数点値のみが対象（それ以外は加算ごとに飽和）
+        let fixed = v1.iter().all(|x| x.format().is_some());
+        let (data, accumulator) = match self.accumulation {
+            AccumulationMode::Wide if fixed => self.accumulate_wide(&v2, v2_wide.as_deref())?,
+            _ => {
+                let data = v1.iter()
+                    .zip(&v2)
+                    .map(|(a, b)| a.saturating_add(b))
+                    .collect::<Result<Vec<_>>>()?;
+                // 加算ごとの飽和はオーバーフロー検査で数える
+                self.saturations += v1.iter()
+                    .zip(&v2)
+                    .filter(|(a, b)| a.format().is_some() && a.checked_add(b).is_err())
+                    .count() as u64;
+                (data, None)
+            }
+        };
+
+        // 累積方式によらずV0には飽和させた同じ値を書き込む（Wideは累積値も保持）
+        self.vector_cache = Some(data.clone());
+        self.accumulator = accumulator;
+        Ok(data)
+    }
+
+    // V0 + V1 をi64で累積し、飽和させた値と累積値を返す
+    //
+    // V1に送り元の拡張精度の部分和が付いていれば、飽和した値ではなくそちらを加える。
+    fn accumulate_wide(
+        &mut self,
+        addend: &[FpgaValue],
+        addend_wide: Option<&[i64]>,
+    ) -> Result<(Vec<FpgaValue>, Option<Vec<i64>>)> {
+        let v0 = self.vector_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        if v0.len() != addend.len() {
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
+        }
+        let format = v0.first().and_then(FpgaValue::format).ok_or_else(|| {
+            FpgaError::Computation("Wide accumulation requires fixed-point values".into())
+        })?;
+        let raw = |x: &FpgaValue| fixed_raw(x, format);
+        let addend = match addend_wide {
+            Some(wide) => wide.to_vec(),
+            None => addend.iter().map(raw).collect::<Result<Vec<_>>>()?,
+        };
 
-        Vector::new(v1.clone())?.add(&Vector::new(v2)?).map(|v| v.data)
+        let mut accumulator = match self.accumulator.take() {
+            Some(acc) => acc,
+            None => v0.iter().map(raw).collect::<Result<Vec<_>>>()?,
+        };
+        accumulator.iter_mut()
+            .zip(&addend)
+            .for_each(|(acc, rhs)| *acc = acc.saturating_add(*rhs));
+        let (data, saturated) = saturate_wide(&accumulator, format);
+        self.saturations += saturated;
+        Ok((data, Some(accumulator)))
     }
 
-    fn vector_relu(&self) -> Result<Vec<FpgaValue>> {
+    // V0とV1の要素ごとの差・積
+    //
+    // 固定小数点はi64で計算して出力フォーマットへ飽和させる（積はMACと同じく
+    // 小数部ビット数分の算術シフト）。それ以外はf32で計算してV0の形式で表す。
+    fn vector_binary(&mut self, op: ComputeOperation) -> Result<Vec<FpgaValue>> {
+        let v0 = self.vector_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+        let v1 = self.shared_memory.read_block(self.id)?;
+        if v0.len() != v1.len() {
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
+        }
+
+        let mut saturated = 0;
+        let data = v0.iter()
+            .zip(&v1)
+            .map(|(a, b)| match *a {
+                FpgaValue::Fixed { value, format } => {
+                    let (a, b) = (value as i64, fixed_raw(b, format)?);
+                    let wide = match op {
+                        ComputeOperation::VectorSub => a - b,
+                        _ => (a * b) >> format.q,
+                    };
+                    let (value, clamped) = FpgaValue::from_wide(wide, format);
+                    saturated += clamped as u64;
+                    Ok(value)
+                }
+                _ => {
+                    let (x, y) = (a.as_f32(), b.as_f32());
+                    Ok(a.with_value(match op {
+                        ComputeOperation::VectorSub => x - y,
+                        _ => x * y,
+                    }))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.saturations += saturated;
+        self.set_vector(data.clone());
+        Ok(data)
+    }
+
+    fn vector_relu(&mut self) -> Result<Vec<FpgaValue>> {
         let vector = self.vector_cache.as_ref()
             .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
-        
-        Vector::new(vector.clone())?.relu().map(|v| v.data)
+
+        let data = Vector::new(vector.clone())?.relu()?.into_data();
+        self.set_vector(data.clone());
+        Ok(data)
+    }
+
+    fn vector_fill(&mut self, value: f32) -> Result<Vec<FpgaValue>> {
+        let (value, saturated) = self.register_zero().with_value_saturating(value);
+        if saturated {
+            self.saturations += MATRIX_SIZE as u64;
+        }
+        let data = vec![value; MATRIX_SIZE];
+        self.set_vector(data.clone());
+        Ok(data)
+    }
+
+    fn vector_scale(&mut self, factor: f32) -> Result<Vec<FpgaValue>> {
+        let vector = self.vector_cache.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Vector not loaded".into()))?;
+
+        // 固定小数点は生の値に係数を掛けて丸め、出力フォーマットへ飽和させる
+        let (data, saturated): (Vec<FpgaValue>, Vec<bool>) = vector.iter()
+            .map(|x| match *x {
+                FpgaValue::Fixed { value, format } => {
+                    FpgaValue::from_wide((value as f64 * factor as f64).round() as i64, format)
+                }
+                _ => (x.with_value(x.as_f32() * factor), false),
+            })
+            .unzip();
+        self.saturations += saturated.iter().filter(|&&s| s).count() as u64;
+        self.set_vector(data.clone());
+        Ok(data)
     }
+
+    fn vector_copy_range(
+        &mut self,
+        source: usize,
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    ) -> Result<Vec<FpgaValue>> {
+        if src_offset + len > MATRIX_SIZE || dst_offset + len > MATRIX_SIZE {
+            return Err(FpgaError::Computation("Copy range out of bounds".into()));
+        }
+        let src = self.shared_memory.read_block(source)?;
+
+        // 未ロードの場合はゼロ初期化されたV0にコピー
+        let zero = self.register_zero();
+        let mut data = self.vector_cache.take()
+            .unwrap_or_else(|| vec![zero; MATRIX_SIZE]);
+        data[dst_offset..dst_offset + len].clone_from_slice(&src[src_offset..src_offset + len]);
+        self.set_vector(data.clone());
+        Ok(data)
+    }
+}
+
+// 固定小数点値の生の値（フォーマットが異なる値・固定小数点以外はエラー）
+fn fixed_raw(x: &FpgaValue, format: QFormat) -> Result<i64> {
+    match *x {
+        FpgaValue::Fixed { value, format: f } if f == format => Ok(value as i64),
+        FpgaValue::Fixed { format: f, .. } => Err(FpgaError::FormatMismatch(format, f)),
+        _ => Err(FpgaError::Computation(format!("Expected a fixed-point value, got {:?}", x))),
+    }
+}
+
+// i64の累積値を出力フォーマットへ飽和させる（件数は飽和した要素の数）
+fn saturate_wide(sums: &[i64], format: QFormat) -> (Vec<FpgaValue>, u64) {
+    let mut saturated = 0;
+    let data = sums.iter()
+        .map(|&sum| {
+            let (value, clamped) = FpgaValue::from_wide(sum, format);
+            saturated += clamped as u64;
+            value
+        })
+        .collect();
+    (data, saturated)
+}
+
+// 固定小数点の行列ベクトル積
+//
+// 積和はi128で保持して小数部ビット数分シフトし、飽和前のi64の値を返す。
+fn multiply_fixed(rows: &[Vec<FpgaValue>], vector: &[FpgaValue], format: QFormat) -> Result<Vec<i64>> {
+    let raw = |values: &[FpgaValue]| values.iter()
+        .map(|x| fixed_raw(x, format).map(|v| v as i32))
+        .collect::<Result<Vec<_>>>();
+    let v = raw(vector)?;
+    rows.iter()
+        .map(|row| {
+            if row.len() != v.len() {
+                return Err(FpgaError::Dimension("Dimension mismatch".into()));
+            }
+            let sum = simd::dot_fixed(&raw(row)?, &v);
+            Ok((sum >> format.q).clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+        })
+        .collect()
 }
 
+/// 演算ユニット群と共有メモリ
+///
+/// ユニットはそれぞれ個別のロックで保護され、`&self` で実行できる演算
+/// （`execute_on`・`execute_fused_on`・`run_on_units`）は対象ユニットのロックのみを
+/// 取得する。異なるユニットへの演算は複数スレッドから同時に発行でき、同じ
+/// ユニットへの演算はそのユニットのロックで直列化される。
 pub struct ComputeCore {
-    units: Vec<ComputeUnit>,
+    units: Vec<Mutex<ComputeUnit>>,
+    shared_memory: Arc<SharedMemory>,
+    health: Mutex<Vec<UnitHealth>>,
+    error_threshold: f64,
+    // run_on_unitsで各ユニットの処理を別スレッドで実行するか
+    concurrent: bool,
 }
 
 impl ComputeCore {
     pub fn new(num_units: usize) -> Result<Self> {
         let shared_memory = Arc::new(SharedMemory::new(num_units));
         let units = (0..num_units)
-            .map(|id| ComputeUnit::new(id, Arc::clone(&shared_memory)))
+            .map(|id| ComputeUnit::new(id, Arc::clone(&shared_memory)).map(Mutex::new))
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(Self { units })
+        Ok(Self {
+            units,
+            shared_memory,
+            health: Mutex::new(vec![UnitHealth::default(); num_units]),
+            error_threshold: DEFAULT_ERROR_THRESHOLD,
+            concurrent: false,
+        })
+    }
+
+    pub fn num_units(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn shared_memory(&self) -> &SharedMemory {
+        &self.shared_memory
     }
 
+    /// ユニットidのロックを取得
+    pub fn unit(&self, id: usize) -> Result<MutexGuard<'_, ComputeUnit>> {
+        self.units.get(id)
+            .ok_or_else(|| FpgaError::Computation("Invalid unit ID".into()))?
+            .lock()
+            .map_err(|_| FpgaError::Computation(format!("Unit {} lock is poisoned", id)))
+    }
+
+    /// ユニットidへの排他参照（ロックを取得せずに参照できる）
     pub fn get_unit(&mut self, id: usize) -> Result<&mut ComputeUnit> {
         self.units.get_mut(id)
+            .ok_or_else(|| FpgaError::Computation("Invalid unit ID".into()))?
+            .get_mut()
+            .map_err(|_| FpgaError::Computation(format!("Unit {} lock is poisoned", id)))
+    }
+
+    // 全ユニットへの排他参照（ロックが壊れていても状態の参照・初期化は行う）
+    fn units_mut(&mut self) -> impl Iterator<Item = &mut ComputeUnit> {
+        self.units.iter_mut().map(|unit| unit.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    // 全ユニットの状態を順に参照
+    fn fold_units<T>(&self, init: T, f: impl Fn(T, &ComputeUnit) -> T) -> T {
+        self.units.iter().fold(init, |acc, unit| {
+            f(acc, &unit.lock().unwrap_or_else(PoisonError::into_inner))
+        })
+    }
+
+    /// 全ユニットの発行統計の合計
+    pub fn issue_stats(&self) -> IssueStats {
+        self.fold_units(IssueStats::default(), |acc, unit| acc.merge(&unit.issue_stats()))
+    }
+
+    pub fn saturations(&self) -> u64 {
+        self.fold_units(0, |acc, unit| acc + unit.saturations())
+    }
+
+    pub fn set_accumulation_mode(&mut self, mode: AccumulationMode) {
+        self.units_mut().for_each(|unit| unit.set_accumulation_mode(mode));
+    }
+
+    pub fn set_vector_format(&mut self, format: Option<QFormat>) {
+        self.units_mut().for_each(|unit| unit.set_vector_format(format));
+    }
+
+    pub fn reset_all(&mut self) -> Result<()> {
+        self.units_mut().try_for_each(|unit| unit.reset())
+    }
+
+    /// ユニットの健全性（切り離しを含む）と飽和数の累計を初期化
+    pub fn reset_statistics(&mut self) {
+        let num_units = self.units.len();
+        *self.health.get_mut().unwrap_or_else(PoisonError::into_inner) = vec![UnitHealth::default(); num_units];
+        self.units_mut().for_each(|unit| unit.saturations = 0);
+    }
+
+    // 切り離されていない全ユニットで実行し、結果を健全性に記録
+    pub fn execute_parallel(&self, op: ComputeOperation) -> Result<Vec<Vec<FpgaValue>>> {
+        let ids = self.available_units();
+        for &id in &ids {
+            self.unit(id)?.check(op)?;
+        }
+        self.run_on_units(&ids, |unit| unit.execute(op))
+    }
+
+    /// ユニットidで演算を実行し、結果を健全性に記録
+    ///
+    /// 引数の誤りやレジスタの未ロードなど呼び出し側に起因するエラーは
+    /// 発行前に返し、ユニットの失敗としては数えない。
+    pub fn execute_on(&self, id: usize, op: ComputeOperation) -> Result<Vec<FpgaValue>> {
+        let mut unit = self.unit(id)?;
+        unit.check(op)?;
+        let result = unit.execute(op);
+        self.record_result(id, result.is_ok());
+        result
+    }
+
+    /// ユニットidで融合命令ワード列を実行し、結果を健全性に記録
+    pub fn execute_fused_on(
+        &self,
+        id: usize,
+        packets: &[FusedPacket],
+        ops: &[VectorOp],
+        block: usize
+    ) -> Result<Vec<FpgaValue>> {
+        let mut unit = self.unit(id)?;
+        unit.check_fused(packets)?;
+        let result = unit.execute_fused(packets, ops, block);
+        self.record_result(id, result.is_ok());
+        result
+    }
+
+    /// run_on_unitsでユニットごとにスレッドを分けて同時に実行するか
+    ///
+    /// デバイスの応答待ちがある命令チャネルではユニット間で待ちが重なる。
+    /// 応答待ちのない命令チャネルではスレッド生成のコストが上回るため既定は無効。
+    pub fn set_concurrent(&mut self, enabled: bool) {
+        self.concurrent = enabled;
+    }
+
+    /// 複数のユニットで同じ処理を実行し、結果をidsの順に返す
+    ///
+    /// 各ユニットの処理はそのユニットのロックのみを取得して行い、
+    /// set_concurrentが有効なら別スレッドで同時に実行する。結果は健全性に
+    /// 記録し、いずれかが失敗した場合は先頭の失敗を返す（呼び出し側に
+    /// 起因するエラーは事前にcheckで検査しておく）。
+    pub fn run_on_units<T, F>(&self, ids: &[usize], f: F) -> Result<Vec<T>>
+    where
+        T: Send,
+        F: Fn(&mut ComputeUnit) -> Result<T> + Sync,
+    {
+        let run = |id: usize| -> Result<T> {
+            let result = f(&mut *self.unit(id)?);
+            self.record_result(id, result.is_ok());
+            result
+        };
+
+        if !self.concurrent || ids.len() <= 1 {
+            return ids.iter().map(|&id| run(id)).collect();
+        }
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = ids.iter().map(|&id| scope.spawn(move || run(id))).collect();
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(FpgaError::Computation(
+                    "Unit worker panicked".into()
+                ))))
+                .collect()
+        })
+    }
+
+    /// 切り離し判定に用いるエラー率閾値（0.0〜1.0）
+    pub fn set_error_threshold(&mut self, threshold: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(FpgaError::Computation(format!(
+                "Error threshold must be between 0 and 1: {}", threshold
+            )));
+        }
+        self.error_threshold = threshold;
+        Ok(())
+    }
+
+    fn health_table(&self) -> MutexGuard<'_, Vec<UnitHealth>> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn health(&self, id: usize) -> Result<UnitHealth> {
+        self.health_table().get(id)
+            .copied()
             .ok_or_else(|| FpgaError::Computation("Invalid unit ID".into()))
     }
 
-    pub fn execute_parallel(&mut self, op: ComputeOperation) -> Result<Vec<Vec<FpgaValue>>> {
-        self.units.iter_mut()
-            .map(|unit| unit.execute(op))
+    // 演算結果を記録し、閾値を超えたユニットを切り離す
+    pub fn record_result(&self, id: usize, success: bool) {
+        let threshold = self.error_threshold;
+        let mut table = self.health_table();
+        let Some(health) = table.get_mut(id) else { return };
+        if success {
+            health.successes += 1;
+        } else {
+            health.failures += 1;
+        }
+
+        let samples = health.successes + health.failures;
+        if !health.blacklisted && samples >= MIN_HEALTH_SAMPLES && health.error_rate() > threshold {
+            health.blacklisted = true;
+            log::warn!(
+                "Unit {} blacklisted: error rate {:.2} exceeds {:.2}",
+                id, health.error_rate(), threshold
+            );
+        }
+    }
+
+    /// 切り離されていないユニットのID
+    pub fn available_units(&self) -> Vec<usize> {
+        self.health_table().iter()
+            .enumerate()
+            .filter(|(_, health)| !health.blacklisted)
+            .map(|(id, _)| id)
             .collect()
     }
-}
\ No newline at end of file
+
+    pub fn num_available_units(&self) -> usize {
+        self.health_table().iter().filter(|h| !h.blacklisted).count()
+    }
+
+    // 切り離し中のユニットを既知解検査で再検査し、正しい結果を返したものを復帰させる
+    pub fn probe_blacklisted(&mut self) -> Vec<usize> {
+        let mut restored = Vec::new();
+        for id in 0..self.units.len() {
+            if !self.health_table()[id].blacklisted {
+                continue;
+            }
+            if matches!(self.get_unit(id).and_then(|unit| unit.known_answer_test()), Ok(true)) {
+                self.health_table()[id] = UnitHealth::default();
+                log::info!("Unit {} restored after probe", id);
+                restored.push(id);
+            }
+        }
+        restored
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::QFormat;
+
+    #[test]
+    fn test_wide_accumulation_mode() -> Result<()> {
+        let format = QFormat::new(23, 8)?;
+        let value = |x: f32| vec![FpgaValue::from_f32(x, format); MATRIX_SIZE];
+        let shared_memory = Arc::new(SharedMemory::new(1));
+
+        // 200 + 200 - 200 は途中でQ23.8の範囲を超える
+        let run = |mode: AccumulationMode| -> Result<(Vec<FpgaValue>, u64)> {
+            let mut unit = ComputeUnit::new(0, Arc::clone(&shared_memory))?;
+            unit.set_accumulation_mode(mode);
+            unit.load_vector(value(200.0))?;
+            shared_memory.write_block(0, value(200.0))?;
+            let first = unit.execute(ComputeOperation::VectorAdd)?;
+            // 途中結果はどちらの方式でも最大値に飽和してV0に書き込まれる
+            assert_eq!(unit.read_vector()?, first);
+            shared_memory.write_block(0, value(-200.0))?;
+            let result = unit.execute(ComputeOperation::VectorAdd)?;
+            assert_eq!(unit.read_vector()?, result);
+            Ok((result, unit.saturations()))
+        };
+
+        // 飽和はどちらの方式でも最初の加算の全要素で数えられる
+        let (wide, wide_saturations) = run(AccumulationMode::Wide)?;
+        let (narrow, narrow_saturations) = run(AccumulationMode::Narrow)?;
+        assert_eq!(wide_saturations, MATRIX_SIZE as u64);
+        assert_eq!(narrow_saturations, MATRIX_SIZE as u64);
+        assert_eq!(wide[0], FpgaValue::from_f32(200.0, format));
+        // Narrowは飽和した最大値（256 - 2^-23）から200を引いた値
+        let saturated = FpgaValue::from_wide(i64::MAX, format).0;
+        assert_eq!(narrow[0], saturated.saturating_add(&FpgaValue::from_f32(-200.0, format))?);
+        assert!((narrow[0].as_f32() - 56.0).abs() < 1e-5);
+        assert!(narrow.iter().all(|x| x.format() == Some(format)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unit_blacklisting() -> Result<()> {
+        let mut core = ComputeCore::new(2)?;
+        for _ in 0..MIN_HEALTH_SAMPLES {
+            core.record_result(1, false);
+            core.record_result(0, true);
+        }
+
+        assert!(core.health(1)?.blacklisted);
+        assert_eq!(core.available_units(), vec![0]);
+        assert_eq!(core.health(0)?.error_rate(), 0.0);
+
+        // 再検査で既知解検査に合格したユニットは統計をリセットして復帰
+        assert_eq!(core.probe_blacklisted(), vec![1]);
+        assert_eq!(core.num_available_units(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caller_errors_not_recorded() -> Result<()> {
+        let mut core = ComputeCore::new(2)?;
+
+        // V0の未ロード・存在しないコピー元は呼び出し側の誤り
+        assert!(core.execute_on(0, ComputeOperation::VectorReLU).is_err());
+        let copy = ComputeOperation::CopyRange { source: 5, src_offset: 0, dst_offset: 0, len: 1 };
+        assert!(core.execute_on(0, copy).is_err());
+        assert_eq!(core.health(0)?, UnitHealth::default());
+
+        core.get_unit(0)?.load_vector(vec![FpgaValue::Float(-1.0); MATRIX_SIZE])?;
+        core.execute_on(0, ComputeOperation::VectorReLU)?;
+        assert_eq!(core.health(0)?.successes, 1);
+        assert_eq!(core.health(0)?.failures, 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn test_register_snapshot() -> Result<()> {
+        let format = QFormat::new(23, 8)?;
+        let mut unit = ComputeUnit::new(0, Arc::new(SharedMemory::new(1)))?;
+        assert_eq!(unit.snapshot().v0, None);
+
+        unit.load_vector(vec![FpgaValue::from_f32(1.5, format); MATRIX_SIZE])?;
+        unit.push_vector()?;
+
+        let snapshot = unit.snapshot();
+        assert_eq!(snapshot.v0, Some(vec![1.5; MATRIX_SIZE]));
+        assert_eq!(snapshot.v1, snapshot.v0);
+        assert_eq!(snapshot.m0, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_validation() -> Result<()> {
+        assert!(ComputeOperation::fill(1.0).is_ok());
+        assert!(ComputeOperation::scale(f32::NAN).is_err());
+        assert!(ComputeOperation::copy_range(0, 8, 0, 8).is_ok());
+        assert!(ComputeOperation::copy_range(0, 8, 0, 9).is_err());
+        assert!(ComputeOperation::copy_range(256, 0, 0, 1).is_err());
+
+        // 存在しない共有メモリブロックはデバイスへ発行する前に検出
+        let mut unit = ComputeUnit::new(0, Arc::new(SharedMemory::new(1)))?;
+        let op = ComputeOperation::copy_range(3, 0, 0, 4)?;
+        assert!(unit.execute(op).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_expr_lowering() -> Result<()> {
+        let format = QFormat::new(23, 8)?;
+        let v = Vector::new(vec![FpgaValue::from_f32(1.0, format); MATRIX_SIZE])?;
+
+        // ReLU + Scale + (PullV1, Add) で4スロットちょうど
+        let expr = VectorExpr::new(v.clone()).relu().scale(0.5).add_vector(&v);
+        let packets = expr.lower();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].operands, vec![0.5f32.to_bits()]);
+
+        // 加算は1命令ワードに1つまで
+        let packets = VectorExpr::new(v.clone()).add_vector(&v).add_vector(&v).lower();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1].ops, 1..2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameterized_activation() -> Result<()> {
+        let relu6 = Activation::ClippedReLU { max: 6.0 };
+        assert_eq!(relu6.apply(-1.0), 0.0);
+        assert_eq!(relu6.apply(8.0), 6.0);
+        assert_eq!(Activation::Elu { alpha: 0.5 }.apply(2.0), 2.0);
+        assert!((Activation::Elu { alpha: 0.5 }.apply(-1.0) + 0.5 * (1.0 - (-1.0f32).exp())).abs() < 1e-6);
+        assert_eq!(Activation::HardSigmoid { slope: 0.2, offset: 0.5 }.apply(5.0), 1.0);
+
+        assert!(Activation::ClippedReLU { max: 0.0 }.validate().is_err());
+        assert!(Activation::Elu { alpha: f32::NAN }.validate().is_err());
+
+        // 係数はSetParamのオペランドとして同じ命令ワードでロードされる
+        let format = QFormat::new(23, 8)?;
+        let v = Vector::new(vec![FpgaValue::from_f32(1.0, format); MATRIX_SIZE])?;
+        let packets = VectorExpr::new(v).activation(relu6).scale(0.5).lower();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].vliw.op1, FpgaInstruction::SetParam);
+        assert_eq!(packets[0].operands, vec![0, 6.0f32.to_bits(), 0, 0.5f32.to_bits()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch_rejects_unloaded_registers() -> Result<()> {
+        let shared_memory = Arc::new(SharedMemory::new(2));
+        let mut unit = ComputeUnit::new(0, shared_memory.clone())?;
+
+        // M0未ロードの乗算・V1未ロードの加算は発行前に拒否され、V0は変わらない
+        let v = vec![FpgaValue::Float(1.0); MATRIX_SIZE];
+        unit.load_vector(v.clone())?;
+        assert!(unit.execute(ComputeOperation::MatrixVectorMultiply).is_err());
+        assert!(unit.execute(ComputeOperation::VectorAdd).is_err());
+        assert_eq!(unit.vector(), Some(v.as_slice()));
+
+        // 相手ユニットが書き出す前の取得はタイムアウトする
+        assert!(unit.pull_vector(1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_context_spill_restore() -> Result<()> {
+        let shared_memory = Arc::new(SharedMemory::new(2));
+        let mut unit = ComputeUnit::new(0, shared_memory.clone())?;
+
+        let rows: Vec<Vec<FpgaValue>> = (0..MATRIX_SIZE)
+            .map(|i| (0..MATRIX_SIZE).map(|j| FpgaValue::Float((i + j) as f32)).collect())
+            .collect();
+        unit.load_matrix(MatrixBlock::new(rows, 0, 0)?)?;
+        unit.load_vector(vec![FpgaValue::Float(1.0); MATRIX_SIZE])?;
+
+        // 退避後に別の処理でユニットを使い、復元して再計算
+        let context = unit.save_context()?;
+        let expected = unit.execute(ComputeOperation::MatrixVectorMultiply)?;
+        unit.reset()?;
+        unit.load_vector(vec![FpgaValue::Float(2.0); MATRIX_SIZE])?;
+        assert!(unit.execute(ComputeOperation::MatrixVectorMultiply).is_err());
+
+        let mut other = ComputeUnit::new(1, shared_memory)?;
+        other.restore_context(&context)?;
+        let restored = other.execute(ComputeOperation::MatrixVectorMultiply)?;
+        assert_eq!(
+            restored.iter().map(|x| x.as_f32()).collect::<Vec<_>>(),
+            expected.iter().map(|x| x.as_f32()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_point_immediates() -> Result<()> {
+        let format = QFormat::new(23, 8)?;
+        let shared_memory = Arc::new(SharedMemory::new(2));
+        let mut unit = ComputeUnit::new(0, shared_memory.clone())?;
+        unit.set_vector_format(Some(format));
+
+        // 未ロードのV0への即値もベクトルレジスタのフォーマットで書き込まれる
+        let filled = unit.execute(ComputeOperation::Fill { value: 1.5 })?;
+        assert!(filled.iter().all(|x| *x == FpgaValue::from_f32(1.5, format)));
+
+        // 拡大は固定小数点のまま行い、範囲外は飽和として数える
+        let scaled = unit.execute(ComputeOperation::Scale { factor: 0.25 })?;
+        assert!(scaled.iter().all(|x| *x == FpgaValue::from_f32(0.375, format)));
+        let saturated = unit.execute(ComputeOperation::Scale { factor: 1000.0 })?;
+        assert!(saturated.iter().all(|x| *x == FpgaValue::from_wide(i64::MAX, format).0));
+        assert_eq!(unit.saturations(), MATRIX_SIZE as u64);
+
+        // 範囲コピーのゼロ埋め部分も同じフォーマット
+        let mut other = ComputeUnit::new(1, shared_memory)?;
+        other.set_vector_format(Some(format));
+        unit.push_vector()?;
+        let copied = other.execute(ComputeOperation::CopyRange {
+            source: 0, src_offset: 0, dst_offset: 0, len: 4,
+        })?;
+        assert!(copied.iter().all(|x| x.format() == Some(format)));
+        assert_eq!(copied[4], FpgaValue::from_f32(0.0, format));
+
+        // 要素ごとの差・積も固定小数点のまま計算し、範囲外は飽和として数える
+        other.load_vector(vec![FpgaValue::from_f32(3.0, format); MATRIX_SIZE])?;
+        other.load_operand(vec![FpgaValue::from_f32(-2.5, format); MATRIX_SIZE])?;
+        let product = other.execute(ComputeOperation::VectorMul)?;
+        assert!(product.iter().all(|x| *x == FpgaValue::from_f32(-7.5, format)));
+        let diff = other.execute(ComputeOperation::VectorSub)?;
+        assert!(diff.iter().all(|x| *x == FpgaValue::from_f32(-5.0, format)));
+        other.load_operand(vec![FpgaValue::from_f32(100.0, format); MATRIX_SIZE])?;
+        let saturated = other.execute(ComputeOperation::VectorMul)?;
+        assert!(saturated.iter().all(|x| *x == FpgaValue::from_wide(i64::MIN, format).0));
+        assert_eq!(other.saturations(), MATRIX_SIZE as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_per_unit_locking() -> Result<()> {
+        let mut core = ComputeCore::new(4)?;
+        for id in 0..4 {
+            core.get_unit(id)?.load_vector(vec![FpgaValue::Float(id as f32); MATRIX_SIZE])?;
+        }
+
+        // 異なるユニットへの演算は共有参照から複数スレッドで同時に発行できる
+        let shared = &core;
+        let results: Vec<Result<Vec<FpgaValue>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|id| scope.spawn(move || shared.execute_on(id, ComputeOperation::Scale { factor: 2.0 })))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for (id, result) in results.into_iter().enumerate() {
+            assert_eq!(result?[0].as_f32(), id as f32 * 2.0);
+        }
+
+        // ユニット0のロックを保持したままでも他のユニットは使える
+        {
+            let _held = core.unit(0)?;
+            core.execute_on(1, ComputeOperation::Scale { factor: 0.5 })?;
+        }
+
+        core.set_concurrent(true);
+        let scaled = core.run_on_units(&[0, 1, 2, 3], |unit| unit.execute(ComputeOperation::Scale { factor: 2.0 }))?;
+        let firsts: Vec<f32> = scaled.iter().map(|v| v[0].as_f32()).collect();
+        assert_eq!(firsts, vec![0.0, 2.0, 8.0, 12.0]);
+        assert_eq!(core.health(3)?.successes, 2);
+        Ok(())
+    }
+}
@@ -1,215 +1,3332 @@
-use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE, DataConverter};
-use crate::memory::MatrixBlock;
+use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE, VECTOR_SIZE, DataConverter, DataFormat};
+use crate::memory::{MatrixBlock, PoolStats, VectorPool, checksum_values};
 use crate::math::{Matrix, Vector};
-use crate::compute::{ComputeCore, ComputeOperation};
-use crate::instructions::{FpgaInstruction, VliwInstruction, InstructionExecutor, FpgaInstructionChannel};
+use crate::compute::{AccumulationMode, Activation, ComputeCore, ComputeOperation, IssueStats, UnitContext, UnitHealth, UnitState, VectorExpr, VectorOp, VectorStats};
+use crate::cache::{CacheStats, HashCache};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// シャドウ実行で検出されたFPGA結果とホスト参照値の不一致
+#[derive(Debug, Clone)]
+pub struct ShadowMismatch {
+    pub matrix_hash: u64,
+    pub vector_hash: u64,
+    pub index: usize,
+    pub device_value: f32,
+    pub host_value: f32,
+}
+
+/// シャドウ実行で観測したデバイスとホスト参照の誤差分布
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ShadowErrorStats {
+    /// 検証した演算数
+    pub operations: u64,
+    /// 比較した要素数
+    pub elements: u64,
+    pub max_abs_error: f32,
+    sum_abs_error: f64,
+    sum_sq_error: f64,
+}
+
+impl ShadowErrorStats {
+    fn record(&mut self, error: f32) {
+        let error = error.abs();
+        self.elements += 1;
+        self.max_abs_error = self.max_abs_error.max(error);
+        self.sum_abs_error += error as f64;
+        self.sum_sq_error += (error as f64).powi(2);
+    }
+
+    pub fn mean_abs_error(&self) -> f64 {
+        if self.elements == 0 { 0.0 } else { self.sum_abs_error / self.elements as f64 }
+    }
+
+    pub fn rms_error(&self) -> f64 {
+        if self.elements == 0 { 0.0 } else { (self.sum_sq_error / self.elements as f64).sqrt() }
+    }
+}
+
+// 行列キャッシュの既定エントリ数
+const DEFAULT_MATRIX_CACHE_SIZE: usize = 8;
+// 中間結果バッファの最大保持数
+const VECTOR_POOL_SIZE: usize = 16;
+
+/// デバイス上のデータ配置（どのデータがどこにロードされているか）
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMap {
+    // 準備済み行列の形状
+    pub matrix_shape: Option<(usize, usize)>,
+    // 各ユニットのM0/V0レジスタの状態
+    pub units: Vec<UnitState>,
+    // 共有メモリの各スロットに有効データがあるか
+    pub shared_memory: Vec<bool>,
+    // ホスト側で再利用待ちの中間結果バッファ数
+    pub pooled_buffers: usize,
+}
+
+// MLPへの入力を参照するための予約名
+pub const MLP_INPUT: &str = "input";
+
+/// 多層パーセプトロンの1層（y = activation(Wx + b) [+ skip]）
+#[derive(Debug, Clone)]
+pub struct MlpLayer {
+    pub weight: Matrix,
+    pub bias: Option<Vector>,
+    pub activation: Option<Activation>,
+    // この層の出力を保存する名前
+    pub save_as: Option<String>,
+    // 出力に加算する保存済み中間結果の名前（残差接続）
+    pub residual_from: Option<String>,
+}
+
+impl MlpLayer {
+    pub fn new(weight: Matrix, bias: Option<Vector>, activation: Option<Activation>) -> Result<Self> {
+        if let Some(b) = &bias {
+            if b.len() != weight.rows() {
+                return Err(FpgaError::Dimension(format!(
+                    "Bias length {} does not match layer output size {}",
+                    b.len(), weight.rows()
+                )));
+            }
+        }
+        Ok(Self {
+            weight,
+            bias,
+            activation,
+            save_as: None,
+            residual_from: None,
+        })
+    }
+
+    /// この層の出力を後段の残差接続用に保存
+    pub fn save_as(mut self, name: &str) -> Self {
+        self.save_as = Some(name.to_string());
+        self
+    }
+
+    /// 保存済みの中間結果（またはMLP_INPUT）を出力に加算
+    pub fn residual_from(mut self, name: &str) -> Self {
+        self.residual_from = Some(name.to_string());
+        self
+    }
+}
+
+/// 行列ベクトル乗算の結果の格納先
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// ホストへ読み出す
+    Host,
+    /// 読み出さずにユニットのV0に残す（出力が1ブロックに収まる場合のみ）
+    Unit(usize),
+}
+
+/// 検証済みの演算（y = activation(Wx + b)）
+///
+/// OperationBuilderでのみ構築でき、FpgaAccelerator::executeで実行する。
+/// バイアスの加算と活性化は結果を保持するユニット上で行う。
+#[derive(Debug, Clone)]
+pub struct Operation {
+    weight: Matrix,
+    bias: Option<Vector>,
+    activation: Option<Activation>,
+    output: OutputTarget,
+}
+
+impl Operation {
+    pub fn output(&self) -> OutputTarget {
+        self.output
+    }
+}
+
+/// 演算の構築
+///
+/// `OperationBuilder::matmul(weight).bias(b).activation(act).output(target).build()`
+/// の形で指定する（bias・activation・outputは省略可）。
+///
+/// バイアスの二重指定・活性化の後のバイアス・活性化の二重指定は型で禁止され、
+/// コンパイルエラーになる。形状や係数の誤りはbuildで個別のエラーになる。
+pub struct OperationBuilder;
+
+impl OperationBuilder {
+    pub fn matmul(weight: Matrix) -> MatmulBuilder<Linear> {
+        MatmulBuilder {
+            weight,
+            bias: None,
+            activation: None,
+            output: OutputTarget::Host,
+            stage: PhantomData,
+        }
+    }
+}
+
+/// MatmulBuilderの段階：バイアス・活性化とも未指定
+pub enum Linear {}
+/// MatmulBuilderの段階：バイアス指定済み
+pub enum Biased {}
+/// MatmulBuilderの段階：活性化指定済み
+pub enum Activated {}
+
+/// 活性化を指定できる段階（バイアスの加算は活性化より前）
+pub trait BeforeActivation {}
+impl BeforeActivation for Linear {}
+impl BeforeActivation for Biased {}
+
+/// 行列ベクトル乗算の構築途中の状態
+pub struct MatmulBuilder<S> {
+    weight: Matrix,
+    bias: Option<Vector>,
+    activation: Option<Activation>,
+    output: OutputTarget,
+    stage: PhantomData<S>,
+}
+
+impl<S> MatmulBuilder<S> {
+    fn into_stage<T>(self) -> MatmulBuilder<T> {
+        MatmulBuilder {
+            weight: self.weight,
+            bias: self.bias,
+            activation: self.activation,
+            output: self.output,
+            stage: PhantomData,
+        }
+    }
+
+    /// 結果の格納先（既定はHost）
+    pub fn output(mut self, output: OutputTarget) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// 形状と係数を検証して演算を生成
+    pub fn build(self) -> Result<Operation> {
+        if let Some(bias) = &self.bias {
+            if bias.len() != self.weight.rows() {
+                return Err(FpgaError::Dimension(format!(
+                    "Bias length {} does not match output size {}",
+                    bias.len(), self.weight.rows()
+                )));
+            }
+        }
+        if let Some(activation) = self.activation {
+            activation.validate()?;
+        }
+        if let OutputTarget::Unit(id) = self.output {
+            if self.weight.rows() > MATRIX_SIZE {
+                return Err(FpgaError::Dimension(format!(
+                    "Output of {} rows does not fit in the V0 register of unit {} ({} elements)",
+                    self.weight.rows(), id, MATRIX_SIZE
+                )));
+            }
+        }
+        Ok(Operation {
+            weight: self.weight,
+            bias: self.bias,
+            activation: self.activation,
+            output: self.output,
+        })
+    }
+}
+
+impl MatmulBuilder<Linear> {
+    /// 乗算結果に加えるバイアス（長さは行列の行数）
+    pub fn bias(mut self, bias: Vector) -> MatmulBuilder<Biased> {
+        self.bias = Some(bias);
+        self.into_stage()
+    }
+}
+
+impl<S: BeforeActivation> MatmulBuilder<S> {
+    /// バイアス加算後に適用する活性化関数
+    pub fn activation(mut self, activation: Activation) -> MatmulBuilder<Activated> {
+        self.activation = Some(activation);
+        self.into_stage()
+    }
+}
+
+/// ブロック疎行列の枝刈り統計
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SparsityStats {
+    pub total_blocks: usize,
+    pub pruned_blocks: usize,
+}
+
+impl SparsityStats {
+    /// 枝刈りにより省略される行列ブロック計算の割合
+    pub fn compute_saved(&self) -> f64 {
+        if self.total_blocks == 0 {
+            0.0
+        } else {
+            self.pruned_blocks as f64 / self.total_blocks as f64
+        }
+    }
+}
+
+/// デバイスで実行できない場合のホスト代替実行ポリシー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FallbackPolicy {
+    /// 常にデバイスで実行し、エラーはそのまま返す
+    #[default]
+    Never,
+    /// デバイス側のハードウェアエラー時のみホストで再計算
+    OnError,
+    /// 常にホストで実行（ボード保守中など）
+    Always,
+}
+
+/// 演算を実際に実行した場所
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionTarget {
+    Device,
+    Host,
+    // 結果キャッシュから返した（今回は計算していない）
+    Cache,
+}
+
+// ウォームアップで各ユニットに実行させる既知解計算の回数
+const WARMUP_ITERATIONS: u32 = 8;
+// 既知解との比較に用いる許容誤差
+const WARMUP_TOLERANCE: f32 = 1e-3;
+
+/// ウォームアップで計測したユニットごとの性能と検証結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitProfile {
+    pub id: usize,
+    /// 1ブロックの行列ベクトル乗算の平均レイテンシ
+    pub latency: Duration,
+    /// 既知解と一致したか
+    pub passed: bool,
+}
+
+impl UnitProfile {
+    /// 1秒あたりのブロック演算数
+    pub fn throughput(&self) -> f64 {
+        let secs = self.latency.as_secs_f64();
+        if secs == 0.0 { f64::INFINITY } else { 1.0 / secs }
+    }
+}
+
+/// ペース制御付き連続実行の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputReport {
+    pub operations: u64,
+    pub elapsed: Duration,
+    pub target_hz: f64,
+    /// 予定時刻から1間隔以上遅れて開始した演算数
+    pub late: u64,
+}
+
+impl ThroughputReport {
+    /// 実測スループット（演算/秒）
+    pub fn achieved_hz(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { self.operations as f64 / secs }
+    }
+}
+
+/// 再帰セルの種類（ゲートの並びはPyTorchのLSTM/GRUと同じ）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecurrentKind {
+    /// 入力・忘却・セル候補・出力ゲート（i, f, g, o）
+    Lstm,
+    /// リセット・更新・新規候補ゲート（r, z, n）
+    Gru,
+}
+
+impl RecurrentKind {
+    fn gates(self) -> usize {
+        match self {
+            RecurrentKind::Lstm => 4,
+            RecurrentKind::Gru => 3,
+        }
+    }
+
+    // パック後の行グループごとの(Wの行を使うか, Uの行を使うか, ゲート番号)
+    //
+    // 和を使うゲートは [W | U] の行で Wx + Uh を求める。GRUの新規候補ゲートは
+    // Uhにのみrを掛けるため、[W | 0] と [0 | U] の2グループに分ける。
+    fn row_groups(self) -> [(bool, bool, usize); 4] {
+        match self {
+            RecurrentKind::Lstm => [(true, true, 0), (true, true, 1), (true, true, 2), (true, true, 3)],
+            RecurrentKind::Gru => [(true, true, 0), (true, true, 1), (true, false, 2), (false, true, 2)],
+        }
+    }
+}
+
+// 隠れ状態の1ブロック分を保持するユニット（いずれもV0に値を保持する）
+#[derive(Debug, Clone, Copy)]
+struct StateUnits {
+    // 現在の隠れ状態
+    hidden: usize,
+    // LSTMはセル状態、GRUは作業用
+    aux: usize,
+    // 新しい隠れ状態の書き込み先（ステップの終わりにhiddenと入れ替える）
+    next: usize,
+}
+
+// 準備済みの再帰セル
+#[derive(Debug, Clone)]
+struct RecurrentCell {
+    kind: RecurrentKind,
+    // 行グループごとに隠れサイズをブロック境界まで埋めた行、列は [x | h]（それぞれブロック境界まで埋める）
+    weight: Matrix,
+    // 行グループごとにまとめたバイアス（weightの行と同じ並び）
+    bias: Option<Vector>,
+    input_size: usize,
+    hidden_size: usize,
+    // 隠れブロックごとの状態ユニット
+    state: Vec<StateUnits>,
+}
+
+/// ユニット間の部分和リダクション順序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReductionOrder {
+    /// ツリー状リダクション（隣接する組を段ごとに加算、段数はlog2）
+    Tree,
+    /// 固定順序の逐次リダクション（ビット再現性あり）
+    Sequential,
+}
 
 pub struct FpgaAccelerator {
     compute_core: ComputeCore,
     data_converter: DataConverter,
     matrix_rows: usize,
     matrix_cols: usize,
-    instruction_channel: FpgaInstructionChannel,
+    prepared_matrix: Option<Matrix>,
+    // 準備済み行列の分割済みブロック（split_blocksの並び順）
+    prepared_blocks: Vec<Matrix>,
+    // 各ユニットのM0にロードされているブロック番号
+    resident: Vec<Option<usize>>,
+    matrix_hash: u64,
+    // ブロックごとの枝刈りフラグ（split_blocksの並び順、trueはスキップ）
+    block_mask: Vec<bool>,
+    // 行列ロード後にブロックのチェックサムを読み戻して検証するか
+    verify_blocks: bool,
+    checksum_failures: Vec<usize>,
+    fallback_policy: FallbackPolicy,
+    last_target: Option<ExecutionTarget>,
+    host_fallbacks: u64,
+    unit_profiles: Vec<UnitProfile>,
+    shadow_tolerance: Option<f32>,
+    shadow_mismatches: Vec<ShadowMismatch>,
+    shadow_sample_rate: f64,
+    shadow_errors: ShadowErrorStats,
+    reduction_order: ReductionOrder,
+    accumulation_mode: AccumulationMode,
+    matrix_cache: HashCache<Vec<Matrix>>,
+    vector_pool: VectorPool,
+    mlp_layers: Vec<MlpLayer>,
+    recurrent: Option<RecurrentCell>,
+    // 再帰セルの状態を保持し、行列ベクトル乗算・ベクトル演算には使わないユニット
+    reserved_units: Vec<usize>,
+    // (行列, 入力ベクトル, 活性化)をキーとする結果キャッシュ（無効時はNone）
+    result_cache: Option<HashCache<Vector>>,
 }
 
 impl FpgaAccelerator {
     pub fn new(num_units: usize, data_converter: DataConverter) -> Result<Self> {
-        Ok(Self {
-            compute_core: ComputeCore::new(num_units)?,
+        // 即値から作るベクトルも変換後のベクトルと同じフォーマットにする
+        let mut compute_core = ComputeCore::new(num_units)?;
+        if let DataFormat::Fixed(format) = data_converter.format() {
+            compute_core.set_vector_format(Some(format));
+        }
+
+        let mut accelerator = Self {
+            compute_core,
             data_converter,
             matrix_rows: 0,
             matrix_cols: 0,
-            instruction_channel: FpgaInstructionChannel::new()?,
+            prepared_matrix: None,
+            prepared_blocks: Vec::new(),
+            resident: vec![None; num_units],
+            matrix_hash: 0,
+            block_mask: Vec::new(),
+            verify_blocks: false,
+            checksum_failures: Vec::new(),
+            fallback_policy: FallbackPolicy::default(),
+            last_target: None,
+            host_fallbacks: 0,
+            unit_profiles: Vec::new(),
+            shadow_tolerance: None,
+            shadow_mismatches: Vec::new(),
+            shadow_sample_rate: 1.0,
+            shadow_errors: ShadowErrorStats::default(),
+            reduction_order: ReductionOrder::Tree,
+            accumulation_mode: AccumulationMode::default(),
+            matrix_cache: HashCache::new(DEFAULT_MATRIX_CACHE_SIZE),
+            vector_pool: VectorPool::new(VECTOR_POOL_SIZE),
+            mlp_layers: Vec::new(),
+            recurrent: None,
+            reserved_units: Vec::new(),
+            result_cache: None,
+        };
+        accelerator.reconcile_unit_state()?;
+        Ok(accelerator)
+    }
+
+    pub fn num_units(&self) -> usize {
+        self.compute_core.num_units()
+    }
+
+    /// 準備済み行列の形状（未準備ならNone）
+    pub fn matrix_shape(&self) -> Option<(usize, usize)> {
+        self.prepared_matrix.as_ref().map(|_| (self.matrix_rows, self.matrix_cols))
+    }
+
+    pub fn unit_state(&self, id: usize) -> Result<UnitState> {
+        Ok(self.compute_core.unit(id)?.state())
+    }
+
+    /// 行列ブロック・ベクトル・共有メモリの配置を取得
+    pub fn memory_map(&self) -> Result<MemoryMap> {
+        let units = (0..self.num_units())
+            .map(|id| self.unit_state(id))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(MemoryMap {
+            matrix_shape: self.matrix_shape(),
+            units,
+            shared_memory: self.compute_core.shared_memory().block_states()?,
+            pooled_buffers: self.vector_pool.available(),
         })
     }
 
-    // ブロードキャストベースの最適化された行列準備処理
-    pub fn prepare_matrix(&mut self, matrix: &Matrix) -> Result<()> {
-        self.matrix_rows = matrix.rows();
-        self.matrix_cols = matrix.cols();
+    /// ユニットのリセット（Noneなら全ユニットと準備済み状態を初期化）
+    ///
+    /// Noneの場合は結果キャッシュ・ユニットの健全性（切り離しを含む）・
+    /// ウォームアップの計測結果・シャドウ実行と飽和の統計も初期化する。
+    ///
+    /// 行列ブロックは全ユニットに分散しているため、単一ユニットの
+    /// リセットでも準備済み行列は無効になる。再帰セルの状態はゼロに戻る。
+    pub fn reset(&mut self, unit: Option<usize>) -> Result<()> {
+        match unit {
+            Some(id) => self.compute_core.get_unit(id)?.reset()?,
+            None => {
+                self.compute_core.reset_all()?;
+                self.compute_core.reset_statistics();
+                self.unit_profiles.clear();
+                self.shadow_mismatches.clear();
+                self.shadow_errors = ShadowErrorStats::default();
+                self.host_fallbacks = 0;
+                self.last_target = None;
+                if let Some(cache) = self.result_cache.as_mut() {
+                    cache.clear();
+                }
+                self.matrix_cache = HashCache::new(self.matrix_cache.capacity());
+                self.vector_pool = VectorPool::new(VECTOR_POOL_SIZE);
+            }
+        }
 
-        // 行列をブロックに分割
-        let blocks = matrix.split_blocks()?;
-        let num_units = self.compute_core.num_units();
-        
-        // 各ブロックグループについて処理
-        for block_group_idx in 0..(blocks.len() + num_units - 1) / num_units {
-            let start_idx = block_group_idx * num_units;
-            let end_idx = std::cmp::min(start_idx + num_units, blocks.len());
-            
-            // このグループの各ブロックを共有メモリを介して配布
-            for block_idx in start_idx..end_idx {
-                self.broadcast_matrix_block(&blocks[block_idx], block_idx)?;
+        self.clear_prepared_matrix();
+        self.reset_recurrent_state()?;
+        self.reconcile_unit_state().map(|_| ())
+    }
+
+    // 準備済み行列の状態を破棄（ユニット上のブロックが失われた場合）
+    fn clear_prepared_matrix(&mut self) {
+        self.prepared_matrix = None;
+        self.prepared_blocks.clear();
+        self.resident.iter_mut().for_each(|r| *r = None);
+        self.matrix_hash = 0;
+        self.block_mask.clear();
+        self.matrix_rows = 0;
+        self.matrix_cols = 0;
+    }
+
+    /// 切り離されていない全ユニットで既知解の計算を行い、性能と正しさを検証
+    ///
+    /// ユニットの行列・ベクトルを上書きするため、終了後はユニットを
+    /// リセットし準備済み行列も破棄する（再帰セルの状態はゼロに戻る）。結果はunit_profiles()でも参照できる。
+    /// 切り離し中のユニットの再検査はprobe_units()で行う。
+    pub fn warmup(&mut self) -> Result<Vec<UnitProfile>> {
+        let matrix_data: Vec<Vec<f32>> = (0..MATRIX_SIZE)
+            .map(|i| (0..MATRIX_SIZE).map(|j| ((i + j) % 3) as f32 - 1.0).collect())
+            .collect();
+        let vector_data: Vec<f32> = (0..MATRIX_SIZE).map(|j| (j % 4) as f32 * 0.5).collect();
+
+        let matrix = Matrix::from_f32(&matrix_data, &self.data_converter)?;
+        let vector = Vector::from_f32(&vector_data, &self.data_converter)?;
+        let expected = matrix.multiply_vector(&vector)?;
+
+        let units = self.compute_core.available_units();
+        let mut profiles = Vec::with_capacity(units.len());
+        for id in units {
+            let start = Instant::now();
+            let passed = match self.warmup_unit(id, &matrix, &vector, &expected) {
+                // 既知解の不一致は健全性に反映し、閾値を超えれば切り離す
+                Ok(outcomes) => {
+                    for &ok in &outcomes {
+                        self.compute_core.record_result(id, ok);
+                    }
+                    outcomes.iter().all(|&ok| ok)
+                }
+                // 実行時のエラーも失敗として記録し、残りのユニットの検証を続ける
+                Err(e) => {
+                    log::error!("Unit {} failed during warm-up: {}", id, e);
+                    self.compute_core.record_result(id, false);
+                    false
+                }
+            };
+            let latency = start.elapsed() / WARMUP_ITERATIONS;
+
+            if !passed {
+                log::error!("Unit {} failed warm-up known-answer test", id);
             }
+            profiles.push(UnitProfile { id, latency, passed });
+        }
+
+        // 途中で失敗したユニットがあってもレジスタと準備済み行列は必ず破棄する
+        self.clear_prepared_matrix();
+        self.unit_profiles = profiles.clone();
+        self.compute_core.reset_all()?;
+        self.reset_recurrent_state()?;
+        Ok(profiles)
+    }
+
+    // 1ユニットでの既知解計算（反復ごとの正否）
+    fn warmup_unit(&mut self, id: usize, matrix: &Matrix, vector: &Vector, expected: &Vector) -> Result<Vec<bool>> {
+        let unit = self.compute_core.get_unit(id)?;
+        unit.load_matrix(MatrixBlock::new(matrix.data().to_vec(), 0, 0)?)?;
+
+        let mut outcomes = Vec::with_capacity(WARMUP_ITERATIONS as usize);
+        for _ in 0..WARMUP_ITERATIONS {
+            // 結果はV0に書き戻されるため毎回入力をロードし直す
+            unit.load_vector(vector.data().to_vec())?;
+            let result = unit.execute(ComputeOperation::MatrixVectorMultiply)?;
+            outcomes.push(result.len() == expected.len()
+                && result.iter()
+                    .zip(expected.data())
+                    .all(|(a, b)| (a.as_f32() - b.as_f32()).abs() <= WARMUP_TOLERANCE));
         }
+        Ok(outcomes)
+    }
 
+    /// ユニットのV0/V1/M0レジスタ内容を取得（debugフィーチャ有効時のみ）
+    #[cfg(feature = "debug")]
+    pub fn register_snapshot(&self, id: usize) -> Result<crate::compute::RegisterSnapshot> {
+        Ok(self.compute_core.unit(id)?.snapshot())
+    }
+
+    /// ユニットのV0/V1/M0をデバイスメモリへ退避
+    pub fn save_unit_context(&mut self, id: usize) -> Result<UnitContext> {
+        self.compute_core.get_unit(id)?.save_context()
+    }
+
+    /// 退避したレジスタ内容をユニットに復元（退避元と異なるユニットも可）
+    pub fn restore_unit_context(&mut self, id: usize, context: &UnitContext) -> Result<()> {
+        self.compute_core.get_unit(id)?.restore_context(context)?;
+        // M0が置き換わるため、次の計算時に割り当てブロックを再ロードする
+        self.resident[id] = None;
         Ok(())
     }
 
-    // ブロックの共有メモリを介したブロードキャスト
-    fn broadcast_matrix_block(&mut self, block: &Matrix, block_idx: usize) -> Result<()> {
-        // Step 1: ブロックをマスターユニット(0)の共有メモリ領域にロード
-        let master_unit = self.compute_core.get_unit(0)?;
-        let matrix_block = MatrixBlock::new(
-            block.data.clone(),
-            block_idx * MATRIX_SIZE,
-            0,
-        )?;
+    pub fn unit_health(&self, id: usize) -> Result<UnitHealth> {
+        self.compute_core.health(id)
+    }
 
-        // マスターユニットにブロックをロード
-        let load_vliw = VliwInstruction::new(
-            FpgaInstruction::LoadM0,    // 行列ブロックをロード
-            FpgaInstruction::PushM0,    // 共有メモリに書き込み
-            FpgaInstruction::Nop,
-            FpgaInstruction::Nop
-        );
-        self.instruction_channel.execute_vliw(load_vliw)?;
-
-        // Step 2: 各ユニットが共有メモリから必要なブロックを取得
-        let pull_vliw = VliwInstruction::new(
-            FpgaInstruction::ZERO_M0,   // まず初期化
-            FpgaInstruction::PULL_M0,   // 共有メモリからブロックを取得
-            FpgaInstruction::Nop,
-            FpgaInstruction::Nop
-        );
+    /// 計算に使用できる（切り離されていない）ユニット数
+    pub fn num_available_units(&self) -> usize {
+        self.compute_core.num_available_units()
+    }
+
+    /// 切り離し中のユニットを既知解検査で再検査し、復帰したユニットのIDを返す
+    ///
+    /// 検査でM0が上書きされるため、対象ユニットには次の計算時に
+    /// 割り当てブロックをロードし直す。
+    pub fn probe_units(&mut self) -> Vec<usize> {
+        for id in 0..self.num_units() {
+            if self.compute_core.health(id).is_ok_and(|h| h.blacklisted) {
+                self.resident[id] = None;
+            }
+        }
+        self.compute_core.probe_blacklisted()
+    }
+
+    /// 直近のウォームアップで計測したユニットごとの性能
+    pub fn unit_profiles(&self) -> &[UnitProfile] {
+        &self.unit_profiles
+    }
+
+    /// 多層パーセプトロンの準備
+    ///
+    /// 全層のブロック分割を行い行列キャッシュに常駐させる。推論時は
+    /// 層ごとにキャッシュ済みブロックを再ロードするだけで再分割は行わない。
+    pub fn prepare_mlp(&mut self, layers: Vec<MlpLayer>) -> Result<()> {
+        if layers.is_empty() {
+            return Err(FpgaError::Configuration("MLP must have at least one layer".into()));
+        }
+        for pair in layers.windows(2) {
+            if pair[0].weight.rows() != pair[1].weight.cols() {
+                return Err(FpgaError::Dimension(format!(
+                    "Layer output size {} does not match next layer input size {}",
+                    pair[0].weight.rows(), pair[1].weight.cols()
+                )));
+            }
+        }
 
-        // 並列にブロックを取得（4ユニットずつ）
-        for unit_group in (0..self.compute_core.num_units()).step_by(4) {
-            let mut group_vliw = pull_vliw.clone();
-            
-            // グループ内の各ユニットに対してPULL命令を設定
-            for i in 0..4 {
-                if unit_group + i < self.compute_core.num_units() {
-                    let unit = self.compute_core.get_unit(unit_group + i)?;
-                    self.instruction_channel.execute_vliw(group_vliw)?;
+        // 残差接続の参照先が前段で保存され、長さが一致するか検証
+        let mut saved: HashMap<&str, usize> = HashMap::new();
+        saved.insert(MLP_INPUT, layers[0].weight.cols());
+        for layer in &layers {
+            if let Some(name) = &layer.residual_from {
+                match saved.get(name.as_str()) {
+                    Some(&len) if len == layer.weight.rows() => {}
+                    Some(&len) => {
+                        return Err(FpgaError::Dimension(format!(
+                            "Residual '{}' has length {} but layer output is {}",
+                            name, len, layer.weight.rows()
+                        )));
+                    }
+                    None => {
+                        return Err(FpgaError::Configuration(format!(
+                            "Residual '{}' is not saved by an earlier layer", name
+                        )));
+                    }
                 }
             }
+            if let Some(name) = &layer.save_as {
+                saved.insert(name.as_str(), layer.weight.rows());
+            }
+        }
+
+        if self.matrix_cache.capacity() < layers.len() {
+            self.matrix_cache.set_capacity(layers.len());
+        }
+        for layer in &layers {
+            let hash = hash_matrix(&layer.weight);
+            if self.matrix_cache.get(hash).is_none() {
+                self.matrix_cache.insert(hash, layer.weight.split_blocks()?);
+            }
         }
 
+        self.mlp_layers = layers;
         Ok(())
     }
 
-    // 最適化された行列ベクトル乗算
-    pub fn compute_matrix_vector(&mut self, vector: &Vector) -> Result<Vector> {
-        if vector.len() != self.matrix_cols {
-            return Err(FpgaError::Computation("Vector size mismatch".into()));
+    /// 準備済みMLPによる推論（最終層の出力のみを返す）
+    pub fn infer(&mut self, input: &Vector) -> Result<Vector> {
+        if self.mlp_layers.is_empty() {
+            return Err(FpgaError::Computation("MLP not prepared".into()));
         }
 
-        // ベクトルをブロックに分割
-        let vector_blocks = vector.split(MATRIX_SIZE)?;
-        let mut final_result = Vec::new();
+        let layers = std::mem::take(&mut self.mlp_layers);
+        let result = self.run_layers(&layers, input);
+        self.mlp_layers = layers;
+        result
+    }
 
-        // 行ブロックごとの処理
-        for block_row in 0..(self.matrix_rows / MATRIX_SIZE) {
-            let units_in_row = std::cmp::min(
-                vector_blocks.len(),
-                self.compute_core.num_units()
-            );
-
-            // ベクトルブロックの配布と計算（ブロードキャスト）
-            self.broadcast_and_compute(
-                &vector_blocks,
-                units_in_row,
-                block_row
-            )?;
+    // 各層を順に実行し、残差接続用の中間結果を推論中のみ保持
+    fn run_layers(&mut self, layers: &[MlpLayer], input: &Vector) -> Result<Vector> {
+        let mut saved: HashMap<&str, Vector> = HashMap::new();
+        saved.insert(MLP_INPUT, input.clone());
 
-            // 結果の収集（ツリー状リダクション）
-            let row_result = self.get_final_result()?;
-            final_result.extend_from_slice(&row_result);
+        let mut x = input.clone();
+        for layer in layers {
+            x = self.run_layer(layer, &x)?;
+            if let Some(name) = &layer.residual_from {
+                let skip = saved.get(name.as_str())
+                    .ok_or_else(|| FpgaError::Computation(format!("Residual '{}' not available", name)))?;
+                x = x.add(skip)?;
+            }
+            if let Some(name) = &layer.save_as {
+                saved.insert(name.as_str(), x.clone());
+            }
         }
+        Ok(x)
+    }
 
-        Vector::new(final_result)
+    // 1層分の計算（バイアスがなければ活性化をデバイス側で融合）
+    fn run_layer(&mut self, layer: &MlpLayer, input: &Vector) -> Result<Vector> {
+        self.prepare_matrix_cached(&layer.weight)?;
+        match &layer.bias {
+            None => self.compute_matrix_vector_with_activation(input, layer.activation),
+            Some(bias) => {
+                let output = self.compute_matrix_vector(input)?.add(bias)?;
+                let mut values: Vec<f32> = output.data().iter().map(|x| x.as_f32()).collect();
+                if let Some(activation) = layer.activation {
+                    activation.apply_all(&mut values);
+                }
+                Vector::new(values.into_iter().map(FpgaValue::Float).collect())
+            }
+        }
     }
 
-    // ベクトルブロックの配布と計算
-    fn broadcast_and_compute(
+    /// 要素ごとのベクトル演算（ReLU・加算・減算・積・Fill・Scale）
+    ///
+    /// ベクトルをMATRIX_SIZE要素のブロックに分割し、利用可能なユニットへ
+    /// 順に割り当てて実行する。ユニット数を超えるブロックは複数の波に分けて
+    /// 処理し、結果はブロック順に再構成する。端数ブロックはゼロで埋めて
+    /// 計算し、出力は入力と同じ長さに切り詰める。2項演算（VectorAdd・VectorSub・
+    /// VectorMul）はoperandが必須で、対応するブロックを各ユニットのV1へ明示的に
+    /// ロードしてから演算する。
+    /// それ以外の演算にoperandを渡すとエラー。
+    pub fn compute_vector_operation(
         &mut self,
-        vector_blocks: &[Vector],
-        units_in_row: usize,
-        block_row: usize
-    ) -> Result<()> {
-        // Step 1: ベクトルをマスターユニットの共有メモリ領域にブロードキャスト
-        let master_vliw = VliwInstruction::new(
-            FpgaInstruction::LoadV0,   // ベクトルをロード
-            FpgaInstruction::PushV0,   // 共有メモリに書き込み
-            FpgaInstruction::Nop,
-            FpgaInstruction::Nop
+        vector: &Vector,
+        operand: Option<&Vector>,
+        op: ComputeOperation,
+    ) -> Result<Vector> {
+        match op {
+            ComputeOperation::MatrixVectorMultiply | ComputeOperation::CopyRange { .. } => {
+                return Err(FpgaError::Computation(format!(
+                    "{:?} is not an element-wise vector operation", op
+                )));
+            }
+            _ => {}
+        }
+        let binary = matches!(
+            op,
+            ComputeOperation::VectorAdd | ComputeOperation::VectorSub | ComputeOperation::VectorMul
         );
-        self.instruction_channel.execute_vliw(master_vliw)?;
+        match (binary, operand) {
+            (true, None) => {
+                return Err(FpgaError::Computation(format!("{:?} requires a second operand", op)));
+            }
+            (true, Some(operand)) if operand.len() != vector.len() => {
+                return Err(FpgaError::Dimension("Vector size mismatch".into()));
+            }
+            (true, Some(_)) | (false, None) => {}
+            (false, Some(_)) => {
+                return Err(FpgaError::Computation(format!("{:?} takes a single operand", op)));
+            }
+        }
+
+        let units = self.compute_units();
+        if units.is_empty() {
+            return Err(FpgaError::Computation("No healthy compute units available".into()));
+        }
 
-        // Step 2: 各ユニットが共有メモリからベクトルを取得し計算
-        for unit_group in (0..units_in_row).step_by(4) {
-            let compute_vliw = VliwInstruction::new(
-                FpgaInstruction::PULL_V0,         // 共有メモリからベクトル取得
-                FpgaInstruction::MatrixVectorMul, // 行列ベクトル乗算実行
-                FpgaInstruction::PushV0,         // 結果を共有メモリに書き戻し
-                FpgaInstruction::Nop
-            );
+        // 端数ブロックは先頭要素と同じデータ形式のゼロで埋める
+        let padded = |block: &[FpgaValue]| {
+            let mut data = block.to_vec();
+            data.resize(MATRIX_SIZE, block[0].with_value(0.0));
+            data
+        };
 
-            // グループ内の各ユニットで並列実行
-            for i in 0..4 {
-                if unit_group + i < units_in_row {
-                    self.instruction_channel.execute_vliw(compute_vliw)?;
+        let mut output = Vec::with_capacity(vector.len());
+        let wave_size = MATRIX_SIZE * units.len();
+        for (w, wave) in vector.data().chunks(wave_size).enumerate() {
+            for (b, (block, &id)) in wave.chunks(MATRIX_SIZE).zip(&units).enumerate() {
+                let unit = self.compute_core.get_unit(id)?;
+                unit.load_vector(padded(block))?;
+                if let Some(operand) = operand {
+                    let start = w * wave_size + b * MATRIX_SIZE;
+                    unit.load_operand(padded(&operand.data()[start..start + block.len()]))?;
                 }
+                let result = self.compute_core.execute_on(id, op)?;
+                output.extend(result.into_iter().take(block.len()));
             }
         }
 
-        // Step 3: ツリー構造でのリダクション
-        let mut active_units = units_in_row;
-        let mut stride = 1;
+        Vector::new(output)
+    }
 
-        while active_units > 1 {
-            for i in 0..(active_units / 2) {
-                let reduction_vliw = VliwInstruction::new(
-                    FpgaInstruction::PULL_V1,     // 共有メモリから第2オペランド取得
-                    FpgaInstruction::VADD_01,     // V0 += V1実行
-                    FpgaInstruction::PushV0,      // 結果を共有メモリに書き戻し
-                    FpgaInstruction::Nop
-                );
-                self.instruction_channel.execute_vliw(reduction_vliw)?;
+    /// LSTM/GRUセルの準備
+    ///
+    /// wは (ゲート数 × hidden) × input、uは (ゲート数 × hidden) × hidden。
+    /// 2つの行列は列方向に結合して行列キャッシュに常駐させ、入力と隠れ状態を
+    /// 連結したベクトルとの1回の行列ベクトル乗算で各ゲートの Wx + Uh を求める。
+    /// GRUの新規候補ゲートのみWxとUhを別の行で求めるため、その分だけ行が増える。
+    ///
+    /// 隠れ状態（LSTMはセル状態も）はユニットのV0に常駐させ、ゲートの活性化・
+    /// アダマール積・加減算もユニットの命令で行う。隠れ状態の16要素ブロックごとに
+    /// 3ユニットを状態の保持用に確保し、残りのユニットで行列ベクトル乗算を行う。
+    /// 確保したユニットは他の計算には使わない。隠れ状態はゼロで初期化され、
+    /// ステップ間で保持される。
+    pub fn prepare_recurrent(
+        &mut self,
+        kind: RecurrentKind,
+        w: &Matrix,
+        u: &Matrix,
+        bias_ih: Option<Vector>,
+        bias_hh: Option<Vector>
+    ) -> Result<()> {
+        let hidden_size = u.cols();
+        let gate_rows = kind.gates() * hidden_size;
+        if w.rows() != gate_rows || u.rows() != gate_rows {
+            return Err(FpgaError::Dimension(format!(
+                "{:?} weights must have {} rows, got W={} U={}",
+                kind, gate_rows, w.rows(), u.rows()
+            )));
+        }
+        for bias in [&bias_ih, &bias_hh].into_iter().flatten() {
+            if bias.len() != gate_rows {
+                return Err(FpgaError::Dimension("Bias size mismatch".into()));
+            }
+        }
+
+        // 状態ユニットと、行列ベクトル乗算用に少なくとも1ユニット
+        let hidden_blocks = hidden_size.div_ceil(MATRIX_SIZE);
+        let units = self.compute_core.available_units();
+        let needed = 3 * hidden_blocks;
+        if units.len() <= needed {
+            return Err(FpgaError::Configuration(format!(
+                "{:?} cell with hidden size {} needs {} compute units, {} available",
+                kind, hidden_size, needed + 1, units.len()
+            )));
+        }
+
+        // 隠れブロック・入力ブロックの境界を揃え、状態ユニットのV0をそのまま
+        // 隠れ状態の列ブロックとして配布できるようにする
+        let input_size = w.cols();
+        let input_width = input_size.div_ceil(MATRIX_SIZE) * MATRIX_SIZE;
+        let hidden_width = hidden_blocks * MATRIX_SIZE;
+        let zero = w.data()[0][0].with_value(0.0);
+        let row = |w_row: Option<&[FpgaValue]>, u_row: Option<&[FpgaValue]>| {
+            let mut row = Vec::with_capacity(input_width + hidden_width);
+            row.extend_from_slice(w_row.unwrap_or_default());
+            row.resize(input_width, zero.clone());
+            row.extend_from_slice(u_row.unwrap_or_default());
+            row.resize(input_width + hidden_width, zero.clone());
+            row
+        };
+
+        let mut rows = Vec::with_capacity(4 * hidden_width);
+        let mut bias = Vec::with_capacity(4 * hidden_width);
+        for (use_w, use_u, gate) in kind.row_groups() {
+            for k in 0..hidden_width {
+                if k >= hidden_size {
+                    rows.push(row(None, None));
+                    bias.push(zero.clone());
+                    continue;
+                }
+                let r = gate * hidden_size + k;
+                rows.push(row(use_w.then(|| &w.data()[r][..]), use_u.then(|| &u.data()[r][..])));
+                bias.push([(use_w, &bias_ih), (use_u, &bias_hh)].into_iter()
+                    .filter_map(|(used, b)| b.as_ref().filter(|_| used))
+                    .try_fold(zero.clone(), |acc, b| acc.saturating_add(&b.data()[r]))?);
             }
+        }
+        let weight = Matrix::new(rows)?;
+        let bias = match (&bias_ih, &bias_hh) {
+            (None, None) => None,
+            _ => Some(Vector::new(bias)?),
+        };
 
-            active_units = (active_units + 1) / 2;
-            stride *= 2;
+        let hash = hash_matrix(&weight);
+        if self.matrix_cache.get(hash).is_none() {
+            self.matrix_cache.insert(hash, weight.split_blocks()?);
         }
 
-        Ok(())
+        self.reserved_units = units[units.len() - needed..].to_vec();
+        let state = self.reserved_units.chunks(3)
+            .map(|ids| StateUnits { hidden: ids[0], aux: ids[1], next: ids[2] })
+            .collect();
+        self.recurrent = Some(RecurrentCell {
+            kind,
+            weight,
+            bias,
+            input_size,
+            hidden_size,
+            state,
+        });
+        self.reset_recurrent_state()
     }
 
-    // 最終結果の取得
-    fn get_final_result(&mut self) -> Result<Vec<FpgaValue>> {
-        let vliw = VliwInstruction::from_single(FpgaInstruction::PULL_V0);
-        self.instruction_channel.execute_vliw(vliw)?;
-        
-        let unit = self.compute_core.get_unit(0)?;
-        match &unit.vector_cache {
-            Some(data) => Ok(data.clone()),
-            None => Err(FpgaError::Computation("No result data available".into()))
+    /// 再帰セルを1ステップ進め、新しい隠れ状態を返す
+    ///
+    /// 途中で失敗した場合の状態は不定のため、reset_recurrent_stateで初期化する。
+    pub fn recurrent_step(&mut self, input: &Vector) -> Result<Vector> {
+        let mut cell = self.recurrent.take()
+            .ok_or_else(|| FpgaError::Computation("Recurrent cell not prepared".into()))?;
+        let result = self.run_recurrent_step(&mut cell, input);
+        self.recurrent = Some(cell);
+        result
+    }
+
+    /// 隠れ状態（LSTMはセル状態も）をゼロに戻す
+    pub fn reset_recurrent_state(&mut self) -> Result<()> {
+        let Some(cell) = self.recurrent.as_ref() else {
+            return Ok(());
+        };
+        let ids: Vec<usize> = cell.state.iter().flat_map(|s| [s.hidden, s.aux]).collect();
+        for id in ids {
+            self.compute_core.execute_on(id, ComputeOperation::Fill { value: 0.0 })?;
         }
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::DataFormat;
+    fn run_recurrent_step(&mut self, cell: &mut RecurrentCell, input: &Vector) -> Result<Vector> {
+        if input.len() != cell.input_size {
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
+        }
+        self.prepare_matrix_cached(&cell.weight)?;
 
-    #[test]
-    fn test_broadcast_matrix_computation() -> Result<()> {
-        let converter = DataConverter::new(DataFormat::Full);
-        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        // 入力はホストから、隠れ状態は状態ユニットのV0から配布する
+        let input_blocks = input.split_padded(MATRIX_SIZE)?;
+        let inputs: Vec<BlockInput> = input_blocks.iter()
+            .map(BlockInput::Host)
+            .chain(cell.state.iter().map(|s| BlockInput::Unit(s.hidden)))
+            .collect();
 
-        // 大きな行列でのテスト（64x64）
-        let matrix_data = vec![vec![1.0; 64]; 64];
-        let vector_data = vec![1.0; 64];
+        // 新しい隠れ状態はnextに書き込み、全ブロックの計算が終わるまでhiddenは変更しない
+        let sigmoid = Some(Activation::Sigmoid);
+        let tanh = Some(Activation::Tanh);
+        for (k, s) in cell.state.iter().enumerate() {
+            match cell.kind {
+                RecurrentKind::Lstm => {
+                    // c = f * c
+                    let f = self.recurrent_gate(cell, &inputs, 1, k, sigmoid)?;
+                    self.combine_from(s.aux, f, ComputeOperation::VectorMul)?;
+                    // c += i * g
+                    let i = self.recurrent_gate(cell, &inputs, 0, k, sigmoid)?;
+                    self.transfer(s.next, i)?;
+                    let g = self.recurrent_gate(cell, &inputs, 2, k, tanh)?;
+                    self.combine_from(s.next, g, ComputeOperation::VectorMul)?;
+                    self.combine_from(s.aux, s.next, ComputeOperation::VectorAdd)?;
+                    // h = o * tanh(c)
+                    self.transfer(s.next, s.aux)?;
+                    self.compute_core.get_unit(s.next)?.activate(Activation::Tanh)?;
+                    let o = self.recurrent_gate(cell, &inputs, 3, k, sigmoid)?;
+                    self.combine_from(s.next, o, ComputeOperation::VectorMul)?;
+                }
+                RecurrentKind::Gru => {
+                    // n = tanh(Wx_n + r * Uh_n)
+                    let r = self.recurrent_gate(cell, &inputs, 0, k, sigmoid)?;
+                    self.transfer(s.next, r)?;
+                    let uh = self.recurrent_gate(cell, &inputs, 3, k, None)?;
+                    self.combine_from(s.next, uh, ComputeOperation::VectorMul)?;
+                    let wx = self.recurrent_gate(cell, &inputs, 2, k, None)?;
+                    self.combine_from(s.next, wx, ComputeOperation::VectorAdd)?;
+                    self.compute_core.get_unit(s.next)?.activate(Activation::Tanh)?;
+                    // h = n + z * (h - n)
+                    self.transfer(s.aux, s.hidden)?;
+                    self.combine_from(s.aux, s.next, ComputeOperation::VectorSub)?;
+                    let z = self.recurrent_gate(cell, &inputs, 1, k, sigmoid)?;
+                    self.combine_from(s.aux, z, ComputeOperation::VectorMul)?;
+                    self.combine_from(s.next, s.aux, ComputeOperation::VectorAdd)?;
+                }
+            }
+        }
 
-        let matrix = Matrix::from_f32(&matrix_data, &converter)?;
-        let vector = Vector::from_f32(&vector_data, &converter)?;
+        let mut output = Vec::with_capacity(cell.state.len() * MATRIX_SIZE);
+        for s in &mut cell.state {
+            std::mem::swap(&mut s.hidden, &mut s.next);
+            output.extend(self.compute_core.get_unit(s.hidden)?.read_vector()?);
+        }
+        output.truncate(cell.hidden_size);
+        Vector::new(output)
+    }
 
-        accelerator.prepare_matrix(&matrix)?;
-        let result = accelerator.compute_matrix_vector(&vector)?;
+    // 行グループgroupの隠れブロックkのゲートを計算し、バイアスと活性化を適用した
+    // 結果をV0に保持するユニットを返す
+    fn recurrent_gate(
+        &mut self,
+        cell: &RecurrentCell,
+        inputs: &[BlockInput],
+        group: usize,
+        k: usize,
+        activation: Option<Activation>
+    ) -> Result<usize> {
+        let block_row = group * cell.state.len() + k;
+        let unit = self.compute_row_resident(inputs, block_row)?;
+        self.apply_bias_activation(unit, cell.bias.as_ref(), block_row, activation)?;
+        Ok(unit)
+    }
 
-        assert_eq!(result.len(), 64);
+    // ユニットのV0にある行ブロックの結果に、バイアスの対応ブロックを加えてから活性化を適用
+    fn apply_bias_activation(
+        &mut self,
+        unit: usize,
+        bias: Option<&Vector>,
+        block_row: usize,
+        activation: Option<Activation>
+    ) -> Result<()> {
+        if let Some(bias) = bias {
+            let mut block: Vec<FpgaValue> = bias.data().iter()
+                .skip(block_row * MATRIX_SIZE)
+                .take(MATRIX_SIZE)
+                .cloned()
+                .collect();
+            block.resize(MATRIX_SIZE, bias.data()[0].with_value(0.0));
+            self.compute_core.get_unit(unit)?.load_operand(block)?;
+            self.compute_core.execute_on(unit, ComputeOperation::VectorAdd)?;
+        }
+        if let Some(act) = activation {
+            self.compute_core.get_unit(unit)?.activate(act)?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// OperationBuilderで構築した演算の実行
+    ///
+    /// 出力先がHostなら結果を返し、Unitなら結果をそのユニットのV0に残してNoneを返す。
+    /// 出力先のユニットは切り離されておらず、再帰セルの状態の保持に使われていない必要がある。
+    pub fn execute(&mut self, op: &Operation, input: &Vector) -> Result<Option<Vector>> {
+        self.last_target = None;
+        if input.len() != op.weight.cols() {
+            return Err(FpgaError::Dimension(format!(
+                "Input length {} does not match matrix columns {}", input.len(), op.weight.cols()
+            )));
+        }
+        if let OutputTarget::Unit(id) = op.output {
+            self.ensure_available(id)?;
+            if self.reserved_units.contains(&id) {
+                return Err(FpgaError::Computation(format!("Unit {} holds recurrent state", id)));
+            }
+        }
+
+        self.prepare_matrix_cached(&op.weight)?;
+        let input_blocks = input.split_padded(MATRIX_SIZE)?;
+        let inputs: Vec<BlockInput> = input_blocks.iter().map(BlockInput::Host).collect();
+
+        let (block_rows, _) = self.block_grid();
+        let mut output = Vec::with_capacity(self.matrix_rows);
+        for block_row in 0..block_rows {
+            let unit = self.compute_row_resident(&inputs, block_row)?;
+            self.apply_bias_activation(unit, op.bias.as_ref(), block_row, op.activation)?;
+            match op.output {
+                OutputTarget::Host => {
+                    let rows = (self.matrix_rows - block_row * MATRIX_SIZE).min(MATRIX_SIZE);
+                    output.extend(self.compute_core.get_unit(unit)?.read_vector()?.into_iter().take(rows));
+                }
+                OutputTarget::Unit(id) if id != unit => self.transfer(id, unit)?,
+                OutputTarget::Unit(_) => {}
+            }
+        }
+
+        self.last_target = Some(ExecutionTarget::Device);
+        match op.output {
+            OutputTarget::Host => Ok(Some(Vector::new(output)?)),
+            OutputTarget::Unit(_) => Ok(None),
+        }
+    }
+
+    // 準備済み行列の行ブロックを計算し、結果を読み出さずにV0に保持するユニットを返す
+    fn compute_row_resident(&mut self, inputs: &[BlockInput], block_row: usize) -> Result<usize> {
+        let units = self.compute_units();
+        if units.is_empty() {
+            return Err(FpgaError::Computation("No healthy compute units available".into()));
+        }
+
+        let (_, blocks_per_row) = self.block_grid();
+        let active_blocks: Vec<(usize, usize)> = (0..blocks_per_row)
+            .filter(|j| !self.block_mask[block_row * blocks_per_row + j])
+            .enumerate()
+            .map(|(i, j)| (j, units[i % units.len()]))
+            .collect();
+        if active_blocks.is_empty() {
+            // 全ブロックが枝刈りされた行の結果はゼロ
+            self.compute_core.execute_on(units[0], ComputeOperation::Fill { value: 0.0 })?;
+            return Ok(units[0]);
+        }
+
+        let mut reducer = None;
+        let mut sink = RowSink::Resident(&mut reducer);
+        if active_blocks.len() <= units.len() {
+            self.compute_chunk(inputs, &active_blocks, block_row, None, MATRIX_SIZE, &mut sink)?;
+        } else {
+            self.stream_row(inputs, &active_blocks, block_row, MATRIX_SIZE, None, &mut sink)?;
+        }
+        reducer.ok_or_else(|| FpgaError::Computation(format!("Row block {} was not computed", block_row)))
+    }
+
+    // 行列ベクトル乗算・ベクトル演算に使うユニット（再帰セルの状態ユニットを除く）
+    fn compute_units(&self) -> Vec<usize> {
+        self.compute_core.available_units()
+            .into_iter()
+            .filter(|id| !self.reserved_units.contains(id))
+            .collect()
+    }
+
+    /// ベクトルの平均・分散・最小・最大を1回の走査で計算
+    ///
+    /// ブロックごとの部分統計を各ユニットで求め、ツリー状に2つずつ併合する。
+    pub fn stats(&mut self, vector: &Vector) -> Result<VectorStats> {
+        let units = self.compute_units();
+        if units.is_empty() {
+            return Err(FpgaError::Computation("No healthy compute units available".into()));
+        }
+
+        let mut partials = Vec::with_capacity(vector.len().div_ceil(MATRIX_SIZE));
+        for (i, block) in vector.data().chunks(MATRIX_SIZE).enumerate() {
+            let mut data = block.to_vec();
+            data.resize(MATRIX_SIZE, FpgaValue::Float(0.0));
+
+            let unit = self.compute_core.get_unit(units[i % units.len()])?;
+            unit.load_vector(data)?;
+            partials.push(unit.partial_stats(block.len())?);
+        }
+
+        // ツリー状リダクション
+        while partials.len() > 1 {
+            partials = partials.chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => a.merge(b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        partials.pop().ok_or_else(|| FpgaError::Computation("Empty vector".into()))
+    }
+
+    /// 遅延ベクトル式の評価
+    ///
+    /// 式全体を一度だけVLIW命令ワード列に変換し、各ブロックをユニットに
+    /// ロードした後はその命令ワード列を続けて発行する。途中結果は
+    /// ユニットのV0に留まり、ホストへは最終結果のみを返す。
+    pub fn evaluate(&mut self, expr: &VectorExpr) -> Result<Vector> {
+        let input = expr.input();
+        for op in expr.ops() {
+            match op {
+                VectorOp::Add(other) if other.len() != input.len() => {
+                    return Err(FpgaError::Dimension("Vector size mismatch".into()));
+                }
+                VectorOp::Activation(act) => act.validate()?,
+                _ => {}
+            }
+        }
+
+        let units = self.compute_units();
+        if units.is_empty() {
+            return Err(FpgaError::Computation("No healthy compute units available".into()));
+        }
+
+        let packets = expr.lower();
+        let mut output = Vec::with_capacity(input.len());
+        for (block_idx, block) in input.data().chunks(MATRIX_SIZE).enumerate() {
+            let mut data = block.to_vec();
+            data.resize(MATRIX_SIZE, block[0].with_value(0.0));
+
+            let id = units[block_idx % units.len()];
+            self.compute_core.get_unit(id)?.load_vector(data)?;
+            let result = self.compute_core.execute_fused_on(id, &packets, expr.ops(), block_idx)?;
+            output.extend(result.into_iter().take(block.len()));
+        }
+
+        Vector::new(output)
+    }
+
+    /// 利用可能な全ユニットのV0をスカラー値で埋める
+    ///
+    /// 値は即値オペランドとしてVectorFill命令に付加されるため、
+    /// ホストで同一値のベクトルを組み立てて転送する必要はない。
+    pub fn broadcast_scalar(&mut self, value: f32) -> Result<()> {
+        let op = ComputeOperation::fill(value)?;
+        self.compute_core.execute_parallel(op).map(|_| ())
+    }
+
+    /// ユニットsrcのV0の一部をユニットdstのV0先頭へ切り出す
+    pub fn slice(&mut self, src: usize, range: Range<usize>, dst: usize) -> Result<Vector> {
+        if range.start > range.end {
+            return Err(FpgaError::Dimension("Invalid slice range".into()));
+        }
+        let copy = ComputeOperation::CopyRange {
+            source: src, src_offset: range.start, dst_offset: 0, len: range.len(),
+        };
+        copy.validate()?;
+        self.ensure_available(dst)?;
+
+        // srcとdstが同一でも元データを失わないよう先に共有メモリへ退避
+        self.compute_core.get_unit(src)?.push_vector()?;
+        self.compute_core.execute_on(dst, ComputeOperation::Fill { value: 0.0 })?;
+        let data = self.compute_core.execute_on(dst, copy)?;
+        Vector::new(data)
+    }
+
+    /// ユニットa, bのV0先頭部分を連結してユニットdstのV0に格納
+    ///
+    /// `a`, `b` は (ユニットID, 要素数)。連結後の長さはVECTOR_SIZE以下。
+    pub fn concat(&mut self, a: (usize, usize), b: (usize, usize), dst: usize) -> Result<Vector> {
+        if a.1 + b.1 > VECTOR_SIZE {
+            return Err(FpgaError::Dimension(format!(
+                "Concatenated length {} exceeds register size {}",
+                a.1 + b.1, VECTOR_SIZE
+            )));
+        }
+        self.ensure_available(dst)?;
+        self.compute_core.get_unit(a.0)?.push_vector()?;
+        self.compute_core.get_unit(b.0)?.push_vector()?;
+
+        self.compute_core.execute_on(dst, ComputeOperation::Fill { value: 0.0 })?;
+        self.compute_core.execute_on(dst, ComputeOperation::CopyRange {
+            source: a.0, src_offset: 0, dst_offset: 0, len: a.1,
+        })?;
+        let data = self.compute_core.execute_on(dst, ComputeOperation::CopyRange {
+            source: b.0, src_offset: 0, dst_offset: a.1, len: b.1,
+        })?;
+        Vector::new(data)
+    }
+
+    // 切り離し中のユニットを演算先に指定された場合はエラー
+    fn ensure_available(&self, id: usize) -> Result<()> {
+        if self.compute_core.health(id)?.blacklisted {
+            return Err(FpgaError::Computation(format!("Unit {} is blacklisted", id)));
+        }
+        Ok(())
+    }
+
+    /// シャドウ実行を有効化（全結果をホスト側のf32参照計算と比較）
+    pub fn enable_shadow_compute(&mut self, tolerance: f32) {
+        self.shadow_tolerance = Some(tolerance);
+    }
+
+    pub fn disable_shadow_compute(&mut self) {
+        self.shadow_tolerance = None;
+    }
+
+    /// シャドウ実行で記録された不一致の一覧
+    pub fn shadow_mismatches(&self) -> &[ShadowMismatch] {
+        &self.shadow_mismatches
+    }
+
+    /// 不一致の記録と誤差統計を消去
+    pub fn clear_shadow_mismatches(&mut self) {
+        self.shadow_mismatches.clear();
+        self.shadow_errors = ShadowErrorStats::default();
+    }
+
+    /// 行列ロード後のブロックチェックサム検証の切り替え
+    ///
+    /// 有効時は各ブロックをユニットへロードした後にChecksumM0でユニットの
+    /// M0を読み戻し、ホストで計算した値と比較する。行列準備時に不一致の
+    /// ブロックがあれば準備済み行列を破棄してエラーを返す。
+    pub fn set_block_verification(&mut self, enabled: bool) {
+        self.verify_blocks = enabled;
+    }
+
+    /// 直近の検証でチェックサムが一致しなかったブロック（split_blocksの並び順）
+    pub fn checksum_failures(&self) -> &[usize] {
+        &self.checksum_failures
+    }
+
+    /// シャドウ実行で検証する演算の割合（0.0〜1.0、既定は全件）
+    ///
+    /// 本番トラフィックでは一部の演算のみを無作為に抽出して検証し、
+    /// 量子化による誤差の傾向をshadow_error_stats()で監視する。
+    pub fn set_shadow_sample_rate(&mut self, rate: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(FpgaError::Configuration(format!(
+                "Shadow sample rate must be between 0 and 1: {}", rate
+            )));
+        }
+        self.shadow_sample_rate = rate;
+        Ok(())
+    }
+
+    pub fn shadow_error_stats(&self) -> ShadowErrorStats {
+        self.shadow_errors
+    }
+
+    /// 決定的実行モードの切り替え
+    ///
+    /// ブロックの割り当てはモードによらず、枝刈りされていないブロックを
+    /// 行優先に数えたg番目のブロックを利用可能ユニットのg % n番目とする。
+    /// 有効時は行ブロック内の部分和を列ブロック順に逐次加算するため、
+    /// スループットは低下するが、同じユニット構成であれば飽和を含む
+    /// 固定小数点の累積結果が実行ごとにビット単位で一致する。
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.reduction_order = if deterministic {
+            ReductionOrder::Sequential
+        } else {
+            ReductionOrder::Tree
+        };
+    }
+
+    pub fn reduction_order(&self) -> ReductionOrder {
+        self.reduction_order
+    }
+
+    /// 部分和の累積方式の切り替え
+    ///
+    /// Wide（既定）ではリダクション中の部分和をi64で保持し、最終出力時に
+    /// のみ飽和させる。Narrowは加算ごとに飽和させる従来の動作。
+    pub fn set_accumulation_mode(&mut self, mode: AccumulationMode) {
+        self.accumulation_mode = mode;
+        self.compute_core.set_accumulation_mode(mode);
+    }
+
+    pub fn accumulation_mode(&self) -> AccumulationMode {
+        self.accumulation_mode
+    }
+
+    /// 行列ブロックの乗算を各ユニットのロックのみを取得して別スレッドで同時に実行するか
+    ///
+    /// ユニットごとの応答待ちが重なるため実機の命令チャネルで有効。
+    /// 結果は無効時と同じ。
+    pub fn set_concurrent_units(&mut self, enabled: bool) {
+        self.compute_core.set_concurrent(enabled);
+    }
+
+    // ブロードキャストベースの最適化された行列準備処理
+    pub fn prepare_matrix(&mut self, matrix: &Matrix) -> Result<()> {
+        // 行列をブロックに分割
+        let blocks = matrix.split_blocks()?;
+        let mask = vec![false; blocks.len()];
+        self.load_blocks(matrix, hash_matrix(matrix), &blocks, mask)
+    }
+
+    /// ブロック単位で枝刈りされた行列の準備
+    ///
+    /// maskはsplit_blocksと同じ行優先のブロック順で、trueのブロックは
+    /// ゼロとみなしてロード・乗算・リダクションをすべて省略する。
+    pub fn prepare_matrix_masked(&mut self, matrix: &Matrix, mask: &[bool]) -> Result<()> {
+        let blocks = matrix.split_blocks()?;
+        if mask.len() != blocks.len() {
+            return Err(FpgaError::Dimension(format!(
+                "Block mask has {} entries but matrix has {} blocks",
+                mask.len(), blocks.len()
+            )));
+        }
+
+        // シャドー検証とハッシュが枝刈り後の内容を反映するようゼロ埋めしておく
+        let pruned = apply_block_mask(matrix, mask)?;
+        self.load_blocks(&pruned, hash_matrix(&pruned), &blocks, mask.to_vec())
+    }
+
+    /// 準備済み行列の一部の行を更新
+    ///
+    /// 更新行を含む行ブロックのみを再配布し、行列全体の再分割・再転送は
+    /// 行わない。枝刈り済みブロックに含まれる値はゼロのまま維持される。
+    pub fn update_prepared_matrix(&mut self, rows: Range<usize>, values: &Matrix) -> Result<()> {
+        let matrix = self.prepared_matrix.as_mut()
+            .ok_or_else(|| FpgaError::Computation("Matrix not prepared".into()))?;
+        if values.rows() != rows.len() || values.cols() != self.matrix_cols || rows.end > self.matrix_rows {
+            return Err(FpgaError::Dimension(format!(
+                "Update of rows {:?} with {}x{} values does not fit {}x{} matrix",
+                rows, values.rows(), values.cols(), self.matrix_rows, self.matrix_cols
+            )));
+        }
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        matrix.set_rows(rows.start, values.data())?;
+        if self.block_mask.iter().any(|&pruned| pruned) {
+            *matrix = apply_block_mask(matrix, &self.block_mask)?;
+        }
+        let matrix = matrix.clone();
+        self.matrix_hash = hash_matrix(&matrix);
+
+        // 更新行を含む行ブロックのみを再分割し、ユニット上にあるものは再ロード
+        let (_, blocks_per_row) = matrix.block_dims();
+        for block_row in rows.start / MATRIX_SIZE..=(rows.end - 1) / MATRIX_SIZE {
+            for block_col in 0..blocks_per_row {
+                let block_idx = block_row * blocks_per_row + block_col;
+                if self.block_mask[block_idx] {
+                    continue;
+                }
+                self.prepared_blocks[block_idx] = matrix.block(block_row, block_col)?;
+                if let Some(id) = self.resident.iter().position(|&r| r == Some(block_idx)) {
+                    self.reload_block(id, block_idx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sparsity_stats(&self) -> SparsityStats {
+        SparsityStats {
+            total_blocks: self.block_mask.len(),
+            pruned_blocks: self.block_mask.iter().filter(|&&pruned| pruned).count(),
+        }
+    }
+
+    /// 内容ハッシュで分割済みブロックを再利用する行列準備処理
+    ///
+    /// 現在ロード済みの行列と同一内容であれば転送自体を省略する。
+    pub fn prepare_matrix_cached(&mut self, matrix: &Matrix) -> Result<()> {
+        let hash = hash_matrix(matrix);
+        if self.prepared_matrix.is_some() && self.matrix_hash == hash {
+            self.matrix_cache.get(hash);
+            return Ok(());
+        }
+
+        let blocks = match self.matrix_cache.get(hash) {
+            Some(blocks) => blocks,
+            None => {
+                let blocks = matrix.split_blocks()?;
+                self.matrix_cache.insert(hash, blocks.clone());
+                blocks
+            }
+        };
+        let mask = vec![false; blocks.len()];
+        self.load_blocks(matrix, hash, &blocks, mask)
+    }
+
+    pub fn set_matrix_cache_size(&mut self, size: usize) {
+        self.matrix_cache.set_capacity(size);
+    }
+
+    pub fn matrix_cache_stats(&self) -> CacheStats {
+        self.matrix_cache.stats()
+    }
+
+    pub fn vector_pool_stats(&self) -> PoolStats {
+        self.vector_pool.stats()
+    }
+
+    // 分割済みブロックを保持し、先頭から利用可能ユニット数分を各ユニットへ配布
+    //
+    // 残りのブロックは計算時に割り当て先ユニットへ順にロードする。
+    fn load_blocks(
+        &mut self,
+        matrix: &Matrix,
+        hash: u64,
+        blocks: &[Matrix],
+        mask: Vec<bool>
+    ) -> Result<()> {
+        self.clear_prepared_matrix();
+        self.matrix_rows = matrix.rows();
+        self.matrix_cols = matrix.cols();
+        self.matrix_hash = hash;
+        self.prepared_matrix = Some(matrix.clone());
+        self.prepared_blocks = blocks.to_vec();
+        self.block_mask = mask;
+
+        self.checksum_failures.clear();
+
+        let units = self.compute_units();
+        let active: Vec<usize> = (0..blocks.len())
+            .filter(|&idx| !self.block_mask[idx])
+            .take(units.len())
+            .collect();
+        for (&block_idx, &id) in active.iter().zip(&units) {
+            if !self.load_block(id, block_idx)? {
+                self.checksum_failures.push(block_idx);
+            }
+        }
+
+        if !self.checksum_failures.is_empty() {
+            self.clear_prepared_matrix();
+            return Err(FpgaError::Memory(format!(
+                "Matrix blocks failed checksum verification: {:?}", self.checksum_failures
+            )));
+        }
+        Ok(())
+    }
+
+    // ブロックをユニットのM0へロード
+    //
+    // 検証が有効な場合はユニットが保持するブロックのチェックサムを読み戻し、
+    // 一致しなければfalseを返す
+    fn load_block(&mut self, id: usize, block_idx: usize) -> Result<bool> {
+        let (_, blocks_per_row) = self.block_grid();
+        let block = &self.prepared_blocks[block_idx];
+        let (row, col) = (block_idx / blocks_per_row * MATRIX_SIZE, block_idx % blocks_per_row * MATRIX_SIZE);
+        // 端のブロックは有効範囲のみを乗算させる
+        let matrix_block = MatrixBlock::new(block.data().to_vec(), row, col)?
+            .with_extent((self.matrix_rows - row).min(MATRIX_SIZE), (self.matrix_cols - col).min(MATRIX_SIZE))?;
+
+        self.compute_core.get_unit(id)?.load_matrix(matrix_block)?;
+        self.resident[id] = Some(block_idx);
+        if !self.verify_blocks {
+            return Ok(true);
+        }
+        self.block_intact(id, block_idx)
+    }
+
+    // 計算・部分更新時のブロックの再ロード（検証に失敗すればエラー）
+    fn reload_block(&mut self, id: usize, block_idx: usize) -> Result<()> {
+        if !self.load_block(id, block_idx)? {
+            self.checksum_failures.push(block_idx);
+            self.resident[id] = None;
+            return Err(FpgaError::Memory(format!(
+                "Matrix block {} failed checksum verification", block_idx
+            )));
+        }
+        Ok(())
+    }
+
+    // ユニットのM0がブロックblock_idxと一致するか（ChecksumM0で読み戻して比較）
+    fn block_intact(&mut self, id: usize, block_idx: usize) -> Result<bool> {
+        let expected = checksum_values(self.prepared_blocks[block_idx].data().iter().flatten());
+        let readback = self.compute_core.get_unit(id)?.matrix_checksum()?;
+        Ok(readback == expected)
+    }
+
+    /// ユニット上にロード済みのブロックのチェックサムを再検証
+    ///
+    /// 不一致のブロックはchecksum_failures()に記録し、次の計算時に
+    /// 割り当て先ユニットへロードし直す。
+    pub fn verify_resident_blocks(&mut self) -> Result<()> {
+        self.checksum_failures.clear();
+        for id in 0..self.resident.len() {
+            if let Some(block_idx) = self.resident[id] {
+                if !self.block_intact(id, block_idx)? {
+                    self.checksum_failures.push(block_idx);
+                    self.resident[id] = None;
+                }
+            }
+        }
+        self.checksum_failures.sort_unstable();
+
+        if !self.checksum_failures.is_empty() {
+            return Err(FpgaError::Memory(format!(
+                "Matrix blocks failed checksum verification: {:?}", self.checksum_failures
+            )));
+        }
+        Ok(())
+    }
+
+    /// ホストが保持するユニットの状態をハードウェアと照合
+    ///
+    /// 常駐と記録しているブロックはユニットの状態とChecksumM0の読み戻しで確認し、
+    /// M0が空または一致しないユニットは記録を消去して次の計算時にロードし直す。
+    /// 再帰セルの状態ユニットのV0が失われていれば状態をゼロに戻す。
+    /// 初期化時とreset()の後に実行され、ボードの電源断などでユニットの内容が
+    /// 失われた場合にも呼び出せる。戻り値は記録を修正したユニット。
+    pub fn reconcile_unit_state(&mut self) -> Result<Vec<usize>> {
+        let mut stale = Vec::new();
+        for id in 0..self.resident.len() {
+            let Some(block_idx) = self.resident[id] else { continue };
+            if !self.unit_state(id)?.matrix_loaded || !self.block_intact(id, block_idx)? {
+                self.resident[id] = None;
+                stale.push(id);
+            }
+        }
+
+        let state_units: Vec<usize> = self.recurrent.as_ref()
+            .map(|cell| cell.state.iter().flat_map(|s| [s.hidden, s.aux]).collect())
+            .unwrap_or_default();
+        let mut lost = Vec::new();
+        for id in state_units {
+            if !self.unit_state(id)?.vector_loaded {
+                lost.push(id);
+            }
+        }
+        if !lost.is_empty() {
+            self.reset_recurrent_state()?;
+            stale.extend(lost);
+        }
+
+        if !stale.is_empty() {
+            log::warn!("Unit state did not match the host view, reconciled units: {:?}", stale);
+        }
+        Ok(stale)
+    }
+
+    // 準備済み行列の(行ブロック数, 列ブロック数)
+    fn block_grid(&self) -> (usize, usize) {
+        (self.matrix_rows.div_ceil(MATRIX_SIZE), self.matrix_cols.div_ceil(MATRIX_SIZE))
+    }
+
+    // 最適化された行列ベクトル乗算
+    pub fn compute_matrix_vector(&mut self, vector: &Vector) -> Result<Vector> {
+        self.compute_matrix_vector_with_activation(vector, None)
+    }
+
+    /// 活性化関数を融合した行列ベクトル乗算
+    ///
+    /// 活性化命令は最終リダクションユニットの結果取得と同じVLIW命令ワードに
+    /// 詰めて発行するため、追加のディスパッチやホスト同期は発生しない。
+    /// 代替実行ポリシーに応じてホストで計算した場合は
+    /// last_execution_target()がHost、結果キャッシュから返した場合はCacheになる。
+    /// エラー時はNone。
+    pub fn compute_matrix_vector_with_activation(
+        &mut self,
+        vector: &Vector,
+        activation: Option<Activation>
+    ) -> Result<Vector> {
+        self.last_target = None;
+        self.check_input(vector, activation)?;
+
+        // 結果の数値を変える設定（累積方式・リダクション順序・代替実行・ブロック検証）もキーに含める
+        let key = self.result_cache.as_ref().map(|_| {
+            let mut hasher = DefaultHasher::new();
+            (self.matrix_hash, hash_values(vector.data()), activation).hash(&mut hasher);
+            (self.accumulation_mode, self.reduction_order, self.fallback_policy, self.verify_blocks).hash(&mut hasher);
+            hasher.finish()
+        });
+        if let (Some(cache), Some(key)) = (self.result_cache.as_mut(), key) {
+            if let Some(cached) = cache.get(key) {
+                self.last_target = Some(ExecutionTarget::Cache);
+                return Ok(cached);
+            }
+        }
+
+        let result = self.with_fallback(
+            |accelerator| accelerator.compute_on_device(vector, activation),
+            |accelerator| accelerator.compute_on_host(vector, activation),
+        );
+        if let (Some(cache), Some(key), Ok(output)) = (self.result_cache.as_mut(), key, &result) {
+            cache.insert(key, output.clone());
+        }
+        result
+    }
+
+    /// 同一の行列・入力・活性化に対する結果のキャッシュを有効化
+    ///
+    /// 行列の内容や、累積方式・リダクション順序・代替実行ポリシー・ブロック検証の
+    /// 設定が変われば別のキーになるため明示的な無効化は不要。
+    /// キャッシュから返した結果はシャドー検証の対象外。
+    pub fn enable_result_cache(&mut self, size: usize, ttl: Option<Duration>) {
+        self.result_cache = Some(HashCache::new(size).with_ttl(ttl));
+    }
+
+    pub fn disable_result_cache(&mut self) {
+        self.result_cache = None;
+    }
+
+    /// 結果キャッシュの統計（無効時はNone）
+    pub fn result_cache_stats(&self) -> Option<CacheStats> {
+        self.result_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// 行列ベクトル乗算の結果のうち上位k要素を(インデックス, 値)の降順で取得
+    ///
+    /// 各行ブロックの結果を保持するユニット上でk個の候補を抽出し、ホストへは
+    /// 候補のみを読み出して併合する。結果キャッシュとシャドー検証の対象外。
+    pub fn compute_top_k(
+        &mut self,
+        vector: &Vector,
+        activation: Option<Activation>,
+        k: usize
+    ) -> Result<Vec<(usize, f32)>> {
+        self.last_target = None;
+        if k == 0 {
+            return Err(FpgaError::Configuration("k must be at least 1".into()));
+        }
+        self.check_input(vector, activation)?;
+
+        let mut candidates = self.with_fallback(
+            |accelerator| {
+                let mut candidates = Vec::new();
+                accelerator.run_on_device(vector, activation, &mut RowSink::TopK(k, &mut candidates))?;
+                Ok(candidates)
+            },
+            |accelerator| {
+                let result = accelerator.compute_on_host(vector, activation)?;
+                Ok(result.data().iter().map(|x| x.as_f32()).enumerate().collect())
+            },
+        )?;
+
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        candidates.truncate(k);
+        Ok(candidates)
+    }
+
+    /// 行列ベクトル乗算の結果の最大要素の(インデックス, 値)
+    pub fn compute_argmax(&mut self, vector: &Vector, activation: Option<Activation>) -> Result<(usize, f32)> {
+        self.compute_top_k(vector, activation, 1)?
+            .pop()
+            .ok_or_else(|| FpgaError::Computation("No result data available".into()))
+    }
+
+    /// ホスト代替実行ポリシーの設定
+    pub fn set_fallback_policy(&mut self, policy: FallbackPolicy) {
+        self.fallback_policy = policy;
+    }
+
+    pub fn fallback_policy(&self) -> FallbackPolicy {
+        self.fallback_policy
+    }
+
+    /// 全ユニットが発行した命令数と命令ワード数の累計
+    pub fn issue_stats(&self) -> IssueStats {
+        self.compute_core.issue_stats()
+    }
+
+    /// ユニットの固定小数点演算（積和・部分和の累積）で飽和した要素数の累計
+    pub fn saturations(&self) -> u64 {
+        self.compute_core.saturations()
+    }
+
+    /// 直近の行列ベクトル乗算を実行した場所（未実行ならNone）
+    pub fn last_execution_target(&self) -> Option<ExecutionTarget> {
+        self.last_target
+    }
+
+    /// ホストで計算された行列ベクトル乗算の累計回数
+    pub fn host_fallbacks(&self) -> u64 {
+        self.host_fallbacks
+    }
+
+    // 準備済み行列に対する入力ベクトルと活性化関数の検証
+    fn check_input(&self, vector: &Vector, activation: Option<Activation>) -> Result<()> {
+        if self.prepared_matrix.is_none() {
+            return Err(FpgaError::Computation("Matrix not prepared".into()));
+        }
+        if vector.len() != self.matrix_cols {
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
+        }
+        if let Some(act) = activation {
+            act.validate()?;
+        }
+        Ok(())
+    }
+
+    // 代替実行ポリシーに従ってデバイスまたはホストで実行
+    fn with_fallback<T>(
+        &mut self,
+        device: impl Fn(&mut Self) -> Result<T>,
+        host: impl Fn(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let result = match self.fallback_policy {
+            FallbackPolicy::Always => host(self),
+            FallbackPolicy::Never => device(self),
+            FallbackPolicy::OnError => match device(self) {
+                Err(e @ (FpgaError::Computation(_) | FpgaError::Memory(_))) => {
+                    log::warn!("Device computation failed, falling back to host: {}", e);
+                    host(self)
+                }
+                result => result,
+            },
+        };
+        match result {
+            Ok(_) if self.last_target == Some(ExecutionTarget::Host) => self.host_fallbacks += 1,
+            Ok(_) => {}
+            Err(_) => self.last_target = None,
+        }
+        result
+    }
+
+    // デバイスと同じ行列・活性化でホスト側で計算
+    fn compute_on_host(&mut self, vector: &Vector, activation: Option<Activation>) -> Result<Vector> {
+        let matrix = self.prepared_matrix.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Matrix not prepared".into()))?;
+        let output = matrix.multiply_vector(vector)?;
+        self.last_target = Some(ExecutionTarget::Host);
+
+        match activation {
+            Some(act) => {
+                let mut values: Vec<f32> = output.data().iter().map(|x| x.as_f32()).collect();
+                act.apply_all(&mut values);
+                Vector::new(values.into_iter().map(FpgaValue::Float).collect())
+            }
+            None => Ok(output),
+        }
+    }
+
+    fn compute_on_device(&mut self, vector: &Vector, activation: Option<Activation>) -> Result<Vector> {
+        let mut values = Vec::with_capacity(self.matrix_rows);
+        self.run_on_device(vector, activation, &mut RowSink::Values(&mut values))?;
+
+        let result = Vector::new(values)?;
+        if let Some(tolerance) = self.shadow_tolerance {
+            if self.shadow_sample_rate >= 1.0 || rand::random::<f64>() < self.shadow_sample_rate {
+                self.verify_with_host(vector, &result, activation, tolerance)?;
+            }
+        }
+        Ok(result)
+    }
+
+    // 行ブロックごとにデバイスで計算し、結果をsinkに渡す
+    fn run_on_device(
+        &mut self,
+        vector: &Vector,
+        activation: Option<Activation>,
+        sink: &mut RowSink
+    ) -> Result<()> {
+        let units = self.compute_units();
+        if units.is_empty() {
+            return Err(FpgaError::Computation("No healthy compute units available".into()));
+        }
+
+        // ベクトルをブロックに分割
+        let vector_blocks = vector.split_padded(MATRIX_SIZE)?;
+        let inputs: Vec<BlockInput> = vector_blocks.iter().map(BlockInput::Host).collect();
+        let blocks_per_row = vector_blocks.len();
+        let zero = vector.data()[0].with_value(0.0);
+
+        // 行ブロックごとの処理
+        //
+        // 枝刈りされていないブロックを行優先に数えた通し番号gをユニット
+        // units[g % n]に割り当てる。行数がブロックの倍数でない場合、
+        // 最終行ブロックのゼロ埋め行はリダクション結果から取り出さない
+        let mut next = 0;
+        let (block_rows, _) = self.block_grid();
+        for block_row in 0..block_rows {
+            let valid_rows = (self.matrix_rows - block_row * MATRIX_SIZE).min(MATRIX_SIZE);
+            // 枝刈りされたブロックに対応するベクトルブロックは配布しない
+            let active_blocks: Vec<(usize, usize)> = (0..blocks_per_row)
+                .filter(|j| !self.block_mask[block_row * blocks_per_row + j])
+                .map(|j| {
+                    let id = units[next % units.len()];
+                    next += 1;
+                    (j, id)
+                })
+                .collect();
+            if active_blocks.is_empty() {
+                // 全ブロックが枝刈りされた行はユニットを使わずに定数で埋める
+                let value = activation.map_or(zero.clone(), |act| zero.with_value(act.apply(0.0)));
+                sink.push_constant(block_row, valid_rows, value);
+                continue;
+            }
+
+            if active_blocks.len() <= units.len() {
+                // 活性化を融合して計算
+                self.compute_chunk(&inputs, &active_blocks, block_row, activation, valid_rows, sink)?;
+            } else {
+                self.stream_row(&inputs, &active_blocks, block_row, valid_rows, activation, sink)?;
+            }
+        }
+
+        self.last_target = Some(ExecutionTarget::Device);
+        Ok(())
+    }
+
+    // ユニット数を超える列ブロックを持つ行の計算
+    //
+    // 先頭のユニット数分の列ブロックを通常どおり計算し、その部分和をリダクション先
+    // ユニットのV0に残す。以降の列ブロックはリダクション先以外のユニットで順に
+    // 計算し、リダクション先を起点にしたリダクションで累積する。累積と活性化は
+    // すべてリダクション先ユニット上で行うため、累積方式・リダクション順序・
+    // 飽和の扱いはユニット数以内の行と同じ。ユニットが1つの場合は累積値を
+    // 自ユニットの共有メモリ領域へ退避してから次のブロックを計算する。
+    fn stream_row(
+        &mut self,
+        inputs: &[BlockInput],
+        active_blocks: &[(usize, usize)],
+        block_row: usize,
+        valid_rows: usize,
+        activation: Option<Activation>,
+        sink: &mut RowSink
+    ) -> Result<()> {
+        let units = self.compute_units();
+        let (first, rest) = active_blocks.split_at(units.len());
+        let reducer = first[0].1;
+        let workers: Vec<usize> = units.iter().copied().filter(|&id| id != reducer).collect();
+
+        self.batched(&units, |this| {
+            this.broadcast_and_compute(inputs, first, block_row)?;
+
+            if workers.is_empty() {
+                for &(block_col, _) in rest {
+                    this.compute_core.get_unit(reducer)?.push_vector()?;
+                    this.broadcast(inputs, &[(block_col, reducer)], block_row)?;
+                    this.compute_core.get_unit(reducer)?.pull_vector(reducer)?;
+                    this.compute_core.execute_on(reducer, ComputeOperation::VectorAdd)?;
+                }
+            } else {
+                for chunk in rest.chunks(workers.len()) {
+                    let blocks: Vec<(usize, usize)> = chunk.iter()
+                        .zip(&workers)
+                        .map(|(&(block_col, _), &id)| (block_col, id))
+                        .collect();
+                    this.broadcast(inputs, &blocks, block_row)?;
+                    let ids: Vec<usize> = std::iter::once(reducer)
+                        .chain(blocks.iter().map(|&(_, id)| id))
+                        .collect();
+                    this.reduce(&ids)?;
+                }
+            }
+
+            this.get_final_result(reducer, sink, activation, block_row, valid_rows)
+        })
+    }
+
+    /// 準備済み行列に対する行列ベクトル乗算を目標レートで連続実行
+    ///
+    /// i番目の演算は開始からi / rate_hz秒後まで待ってから発行する。
+    /// 処理が追いつかない場合は待たずに次を発行し（まとめて追い上げる
+    /// バーストはしない）、遅延した演算数を報告する。結果はsinkに渡す。
+    pub fn compute_paced<I, F>(&mut self, vectors: I, rate_hz: f64, mut sink: F) -> Result<ThroughputReport>
+    where
+        I: IntoIterator<Item = Vector>,
+        F: FnMut(Vector),
+    {
+        if !(rate_hz > 0.0 && rate_hz.is_finite()) {
+            return Err(FpgaError::Configuration(format!("Invalid target rate: {}", rate_hz)));
+        }
+
+        let interval = Duration::from_secs_f64(1.0 / rate_hz);
+        let start = Instant::now();
+        let mut next = start;
+        let mut operations = 0;
+        let mut late = 0;
+
+        for vector in vectors {
+            let now = Instant::now();
+            if now < next {
+                std::thread::sleep(next - now);
+            } else if now > next + interval {
+                late += 1;
+            }
+            next = next.max(now) + interval;
+
+            sink(self.compute_matrix_vector(&vector)?);
+            operations += 1;
+        }
+
+        Ok(ThroughputReport {
+            operations,
+            elapsed: start.elapsed(),
+            target_hz: rate_hz,
+            late,
+        })
+    }
+
+    // ホスト側の参照計算と比較し、許容誤差を超えた要素を記録
+    fn verify_with_host(
+        &mut self,
+        vector: &Vector,
+        result: &Vector,
+        activation: Option<Activation>,
+        tolerance: f32
+    ) -> Result<()> {
+        let matrix = self.prepared_matrix.as_ref()
+            .ok_or_else(|| FpgaError::Computation("Matrix not prepared".into()))?;
+        let reference = matrix.multiply_vector(vector)?;
+        let vector_hash = hash_values(vector.data());
+        self.shadow_errors.operations += 1;
+
+        for (index, (device, host)) in result.data().iter()
+            .zip(reference.data().iter())
+            .enumerate()
+        {
+            let device_value = device.as_f32();
+            let host_value = activation.map_or(host.as_f32(), |a| a.apply(host.as_f32()));
+            self.shadow_errors.record(device_value - host_value);
+            if (device_value - host_value).abs() > tolerance {
+                log::warn!(
+                    "Shadow mismatch at {}: device={} host={}",
+                    index, device_value, host_value
+                );
+                self.shadow_mismatches.push(ShadowMismatch {
+                    matrix_hash: self.matrix_hash,
+                    vector_hash,
+                    index,
+                    device_value,
+                    host_value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // 行ブロック内の1チャンク分の計算とリダクション、結果の取得
+    //
+    // 各ユニットの命令はチャンクの処理が終わるまで蓄積し、依存関係を保った
+    // まま命令ワードに詰めてから発行する。
+    fn compute_chunk(
+        &mut self,
+        inputs: &[BlockInput],
+        blocks: &[(usize, usize)],
+        block_row: usize,
+        activation: Option<Activation>,
+        rows: usize,
+        sink: &mut RowSink
+    ) -> Result<()> {
+        let ids: Vec<usize> = blocks.iter().map(|&(_, id)| id).collect();
+        self.batched(&ids, |this| {
+            let reducer = this.broadcast_and_compute(inputs, blocks, block_row)?;
+            this.get_final_result(reducer, sink, activation, block_row, rows)
+        })
+    }
+
+    // ユニット群の命令をバッチ発行で命令ワードに詰めて実行
+    fn batched<T>(&mut self, ids: &[usize], run: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        for &id in ids {
+            self.compute_core.get_unit(id)?.begin_batch();
+        }
+
+        let result = run(self);
+
+        // 失敗時も検証済みの命令は発行し、バッチ発行を終了させる
+        let mut flushed = Ok(());
+        for &id in ids {
+            flushed = flushed.and(self.compute_core.get_unit(id).and_then(|unit| unit.flush()));
+        }
+        let value = result?;
+        flushed.map(|_| value)
+    }
+
+    // ベクトルブロックの配布と計算
+    //
+    // inputsは列ブロックごとのベクトルブロックの取得元、blocksは(列ブロック番号, 割り当てユニット)。各ユニットは自身の行列
+    // ブロックと対応するベクトルブロックの積を求め、部分和をリダクションした
+    // ユニットのIDを返す。
+    fn broadcast_and_compute(
+        &mut self,
+        inputs: &[BlockInput],
+        blocks: &[(usize, usize)],
+        block_row: usize
+    ) -> Result<usize> {
+        self.broadcast(inputs, blocks, block_row)?;
+        let ids: Vec<usize> = blocks.iter().map(|&(_, id)| id).collect();
+        self.reduce(&ids)?;
+        Ok(ids[0])
+    }
+
+    // 各ユニットで行列ブロックとベクトルブロックの積を計算（結果は各ユニットのV0）
+    fn broadcast(
+        &mut self,
+        inputs: &[BlockInput],
+        blocks: &[(usize, usize)],
+        block_row: usize
+    ) -> Result<()> {
+        let (_, blocks_per_row) = self.block_grid();
+        for &(block_col, id) in blocks {
+            // 割り当て先に該当ブロックが残っていなければロードし直す
+            let block_idx = block_row * blocks_per_row + block_col;
+            if self.resident[id] != Some(block_idx) {
+                self.reload_block(id, block_idx)?;
+            }
+
+            match inputs[block_col] {
+                BlockInput::Host(block) => self.compute_core.get_unit(id)?.load_vector(block.data().to_vec())?,
+                BlockInput::Unit(source) => self.transfer(id, source)?,
+            }
+            self.compute_core.get_unit(id)?.check(ComputeOperation::MatrixVectorMultiply)?;
+        }
+
+        // 乗算は各ユニットのロックのみを取得して同時に実行する
+        let ids: Vec<usize> = blocks.iter().map(|&(_, id)| id).collect();
+        self.compute_core.run_on_units(&ids, |unit| unit.execute(ComputeOperation::MatrixVectorMultiply))?;
+        Ok(())
+    }
+
+    // ids[0]のV0へ残りのユニットの部分和をリダクション順序に従って加算
+    fn reduce(&mut self, ids: &[usize]) -> Result<()> {
+        match self.reduction_order {
+            ReductionOrder::Tree => self.reduce_tree(ids),
+            ReductionOrder::Sequential => self.reduce_sequential(ids),
+        }
+    }
+
+    // ツリー構造でのリダクション（隣接するユニットの組を段ごとに加算）
+    fn reduce_tree(&mut self, ids: &[usize]) -> Result<()> {
+        let mut stride = 1;
+        while stride < ids.len() {
+            for i in (0..ids.len() - stride).step_by(2 * stride) {
+                self.accumulate_from(ids[i], ids[i + stride])?;
+            }
+            stride *= 2;
+        }
+        Ok(())
+    }
+
+    // 固定順序での逐次リダクション（先頭ユニットに残りを順番に加算）
+    fn reduce_sequential(&mut self, ids: &[usize]) -> Result<()> {
+        for &source in &ids[1..] {
+            self.accumulate_from(ids[0], source)?;
+        }
+        Ok(())
+    }
+
+    // ユニットsourceの部分和をユニットdstのV0に加算
+    fn accumulate_from(&mut self, dst: usize, source: usize) -> Result<()> {
+        self.combine_from(dst, source, ComputeOperation::VectorAdd)
+    }
+
+    // ユニットsourceのV0を第2オペランドとして、ユニットdstのV0に2項演算opを適用
+    fn combine_from(&mut self, dst: usize, source: usize, op: ComputeOperation) -> Result<()> {
+        self.compute_core.get_unit(source)?.push_vector()?;
+        self.compute_core.get_unit(dst)?.pull_vector(source)?;
+        self.compute_core.execute_on(dst, op)?;
+        Ok(())
+    }
+
+    // ユニットsourceのV0をユニットdstのV0へ共有メモリ経由で転送（sourceのV0は残る）
+    fn transfer(&mut self, dst: usize, source: usize) -> Result<()> {
+        self.compute_core.get_unit(source)?.push_vector()?;
+        self.compute_core.get_unit(dst)?.pull_vector_to_v0(source)
+    }
+
+    // 最終結果の取得
+    //
+    // リダクション先ユニットで活性化を適用してから、先頭rows要素のみを
+    // 対象に読み出すか上位k要素を抽出する（端のブロックのゼロ埋め行は除く）
+    fn get_final_result(
+        &mut self,
+        reducer: usize,
+        sink: &mut RowSink,
+        activation: Option<Activation>,
+        block_row: usize,
+        rows: usize
+    ) -> Result<()> {
+        let unit = self.compute_core.get_unit(reducer)?;
+        if let Some(act) = activation {
+            unit.activate(act)?;
+        }
+        match sink {
+            RowSink::Values(output) => {
+                let data = unit.read_vector()?;
+                output.extend_from_slice(&data[..rows.min(data.len())]);
+            }
+            RowSink::TopK(k, candidates) => {
+                candidates.extend(unit.top_k(*k, rows)?
+                    .into_iter()
+                    .map(|(i, value)| (block_row * MATRIX_SIZE + i, value)));
+            }
+            RowSink::Resident(unit) => **unit = Some(reducer),
+        }
+        Ok(())
+    }
+}
+
+// 行ブロックごとの計算結果の受け取り方
+enum RowSink<'a> {
+    // 有効行の値を読み出して連結する
+    Values(&'a mut Vec<FpgaValue>),
+    // 結果を保持するユニット上で上位k要素を抽出し、(行インデックス, 値)のみを読み出す
+    TopK(usize, &'a mut Vec<(usize, f32)>),
+    // 結果を読み出さず、結果をV0に保持するリダクション先ユニットのIDのみを受け取る
+    Resident(&'a mut Option<usize>),
+}
+
+// 列ブロックに対応するベクトルブロックの取得元
+enum BlockInput<'a> {
+    // ホストから転送する
+    Host(&'a Vector),
+    // ユニットのV0に常駐するベクトルを共有メモリ経由で受け取る
+    Unit(usize),
+}
+
+impl RowSink<'_> {
+    // 全要素が同じ値の行（ユニットでの計算を省略した行）
+    fn push_constant(&mut self, block_row: usize, rows: usize, value: FpgaValue) {
+        match self {
+            RowSink::Values(output) => output.extend(std::iter::repeat_n(value, rows)),
+            RowSink::TopK(k, candidates) => {
+                let value = value.as_f32();
+                candidates.extend((0..rows.min(*k)).map(|i| (block_row * MATRIX_SIZE + i, value)));
+            }
+            // 常駐させる行は枝刈りされていてもユニットで計算する（compute_row_resident）
+            RowSink::Resident(_) => unreachable!("resident rows are always computed on a unit"),
+        }
+    }
+}
+
+// 枝刈り対象ブロックをゼロで置き換えた行列を生成
+fn apply_block_mask(matrix: &Matrix, mask: &[bool]) -> Result<Matrix> {
+    let (_, blocks_per_row) = matrix.block_dims();
+    let data = matrix.data().iter()
+        .enumerate()
+        .map(|(i, row)| row.iter()
+            .enumerate()
+            .map(|(j, x)| {
+                if mask[(i / MATRIX_SIZE) * blocks_per_row + j / MATRIX_SIZE] {
+                    FpgaValue::Float(0.0)
+                } else {
+                    x.clone()
+                }
+            })
+            .collect())
+        .collect();
+    Matrix::new(data)
+}
+
+// 行列の形状と内容から計算するハッシュ値
+fn hash_matrix(matrix: &Matrix) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (matrix.rows(), matrix.cols()).hash(&mut hasher);
+    hash_values(matrix.data().iter().flatten()).hash(&mut hasher);
+    hasher.finish()
+}
+
+// オペランドのハッシュ値（不一致記録の識別用）
+fn hash_values<'a>(values: impl IntoIterator<Item = &'a FpgaValue>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for value in values {
+        value.as_f32().to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::QFormat;
+
+    #[test]
+    fn test_broadcast_matrix_computation() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // 大きな行列でのテスト（64x64）
+        let matrix_data = vec![vec![1.0; 64]; 64];
+        let vector_data = vec![1.0; 64];
+
+        let matrix = Matrix::from_f32(&matrix_data, &converter)?;
+        let vector = Vector::from_f32(&vector_data, &converter)?;
+
+        accelerator.prepare_matrix(&matrix)?;
+        let result = accelerator.compute_matrix_vector(&vector)?;
+
+        assert_eq!(result.len(), 64);
+        assert!(result.data().iter().all(|x| x.as_f32() == 64.0));
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        Ok(())
+    }
+
+    #[test]
+    fn test_instruction_packing_on_device() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 32]; 16], &converter)?)?;
+
+        // LoadV0・乗算・部分和の転送・加算・活性化・読み出しを命令ワードに詰めて発行する
+        let before = accelerator.issue_stats();
+        let result = accelerator.compute_matrix_vector_with_activation(
+            &Vector::from_f32(&[1.0; 32], &converter)?,
+            Some(Activation::ReLU)
+        )?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 32.0));
+
+        let after = accelerator.issue_stats();
+        let instructions = after.instructions - before.instructions;
+        let packets = after.packets - before.packets;
+        assert_eq!(instructions, 9);
+        assert_eq!(packets, 3);
+        // 部分和の取得前のWaitFlagはシミュレータ内でのみ実行される
+        assert_eq!(after.simulated - before.simulated, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_point_device_computation() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // 16ブロック（4x4）を4ユニットで順にロードし直しながら計算する
+        let matrix_data: Vec<Vec<f32>> = (0..64)
+            .map(|i| (0..64).map(|j| if i == j { 0.5 } else { 0.0 }).collect())
+            .collect();
+        let vector_data: Vec<f32> = (0..64).map(|j| j as f32 * 0.125).collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&matrix_data, &converter)?)?;
+
+        let result = accelerator.compute_matrix_vector(&Vector::from_f32(&vector_data, &converter)?)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        for (j, x) in result.data().iter().enumerate() {
+            assert!(x.format().is_some());
+            assert_eq!(x.as_f32(), j as f32 * 0.0625);
+        }
+        assert_eq!(accelerator.saturations(), 0);
+
+        // 各行の積和（16 x 100）はQ23.8の範囲を超えて飽和する
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?)?;
+        let result = accelerator.compute_matrix_vector(&Vector::from_f32(&[100.0; 16], &converter)?)?;
+        assert!(result.data().iter().all(|x| (x.as_f32() - 256.0).abs() < 1e-3));
+        assert_eq!(accelerator.saturations(), 16);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shadow_compute() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+
+        let matrix = Matrix::from_f32(&vec![vec![0.5; 32]; 32], &converter)?;
+        let vector = Vector::from_f32(&[2.0; 32], &converter)?;
+
+        accelerator.prepare_matrix(&matrix)?;
+        accelerator.compute_matrix_vector(&vector)?;
+
+        assert!(accelerator.shadow_mismatches().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deterministic_reduction() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.set_accumulation_mode(AccumulationMode::Narrow);
+        accelerator.set_deterministic(true);
+        assert_eq!(accelerator.reduction_order(), ReductionOrder::Sequential);
+
+        // 列ブロックごとの部分和は +200, +200, -200, -200（Q23.8は±256で飽和）
+        let row: Vec<f32> = (0..64).map(|j| if j < 32 { 12.5 } else { -12.5 }).collect();
+        let matrix = Matrix::from_f32(&vec![row; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 64], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        // 列ブロック順の逐次加算: ((200 + 200) → 256) - 200 - 200
+        let first = accelerator.compute_matrix_vector(&vector)?;
+        let second = accelerator.compute_matrix_vector(&vector)?;
+        for (a, b) in first.data().iter().zip(second.data().iter()) {
+            assert_eq!(a.raw(), b.raw());
+            assert!((a.as_f32() + 144.0).abs() < 1e-3);
+        }
+
+        // ツリー状リダクション: (200 + 200 → 256) + (-200 - 200 → -256)
+        accelerator.set_deterministic(false);
+        let tree = accelerator.compute_matrix_vector(&vector)?;
+        assert!(tree.data().iter().all(|x| x.as_f32().abs() < 1e-3));
+
+        // Wideは部分和を飽和させずにユニット間で受け渡すため、どちらの順序でも0
+        accelerator.set_accumulation_mode(AccumulationMode::Wide);
+        for deterministic in [true, false] {
+            accelerator.set_deterministic(deterministic);
+            let result = accelerator.compute_matrix_vector(&vector)?;
+            assert!(result.data().iter().all(|x| x.raw() == Some(0)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_accumulation_overflowing_block() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // 列ブロックごとの部分和は +300（単独でQ23.8の範囲を超える）と -200
+        let row: Vec<f32> = (0..32).map(|j| if j < 16 { 18.75 } else { -12.5 }).collect();
+        let matrix = Matrix::from_f32(&vec![row; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 32], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        for deterministic in [true, false] {
+            accelerator.set_deterministic(deterministic);
+            accelerator.set_accumulation_mode(AccumulationMode::Wide);
+            let wide = accelerator.compute_matrix_vector(&vector)?;
+            assert!(wide.data().iter().all(|x| x.as_f32() == 100.0));
+
+            // Narrowはブロックの積和が256に飽和してから加算される
+            accelerator.set_accumulation_mode(AccumulationMode::Narrow);
+            let narrow = accelerator.compute_matrix_vector(&vector)?;
+            assert!(narrow.data().iter().all(|x| (x.as_f32() - 56.0).abs() < 1e-3));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_row_accumulates_on_unit() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+
+        // 列ブロックの部分和は +160, +160, -128, -128（前半2ブロックの和が範囲を超える）
+        let row: Vec<f32> = (0..64).map(|j| if j < 32 { 10.0 } else { -8.0 }).collect();
+        let matrix = Matrix::from_f32(&vec![row; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 64], &converter)?;
+
+        // 列ブロック数がユニット数を超えるため、ユニット上の累積値に順に加算される
+        for num_units in [1, 2] {
+            let mut accelerator = FpgaAccelerator::new(num_units, converter.clone())?;
+            accelerator.prepare_matrix(&matrix)?;
+            for deterministic in [true, false] {
+                accelerator.set_deterministic(deterministic);
+                accelerator.set_accumulation_mode(AccumulationMode::Wide);
+                let wide = accelerator.compute_matrix_vector_with_activation(&vector, Some(Activation::ReLU))?;
+                assert!(wide.data().iter().all(|x| x.as_f32() == 64.0));
+                assert!(wide.data().iter().all(|x| x.format().is_some()));
+
+                // Narrowは前半の和が256に飽和してから後半が加算される
+                accelerator.set_accumulation_mode(AccumulationMode::Narrow);
+                let before = accelerator.saturations();
+                let narrow = accelerator.compute_matrix_vector(&vector)?;
+                assert!(narrow.data().iter().all(|x| x.as_f32().abs() < 1e-3));
+                assert_eq!(accelerator.saturations() - before, 16);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_units() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let matrix_data: Vec<Vec<f32>> = (0..48)
+            .map(|i| (0..80).map(|j| ((i * 7 + j * 3) % 11) as f32 * 0.25 - 1.0).collect())
+            .collect();
+        let vector_data: Vec<f32> = (0..80).map(|j| (j % 5) as f32 * 0.5 - 1.0).collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&matrix_data, &converter)?)?;
+        let vector = Vector::from_f32(&vector_data, &converter)?;
+
+        // ユニットごとに別スレッドで乗算しても結果は変わらない
+        let sequential = accelerator.compute_matrix_vector(&vector)?;
+        accelerator.set_concurrent_units(true);
+        let concurrent = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(concurrent.data(), sequential.data());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepare_matrix_cached() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.set_matrix_cache_size(1);
+
+        let a = Matrix::from_f32(&vec![vec![1.0; 32]; 32], &converter)?;
+        let b = Matrix::from_f32(&vec![vec![2.0; 32]; 32], &converter)?;
+
+        accelerator.prepare_matrix_cached(&a)?;
+        accelerator.prepare_matrix_cached(&a)?;
+        accelerator.prepare_matrix_cached(&b)?;
+        accelerator.prepare_matrix_cached(&a)?;
+
+        let stats = accelerator.matrix_cache_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.evictions, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_map() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let map = accelerator.memory_map()?;
+        assert_eq!(map.matrix_shape, None);
+        assert_eq!(map.units.len(), 4);
+        assert_eq!(map.shared_memory, vec![false; 4]);
+
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 32]; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        assert_eq!(accelerator.memory_map()?.matrix_shape, Some((16, 32)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 32]; 32], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 32], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        accelerator.reset(Some(1))?;
+        assert_eq!(accelerator.matrix_shape(), None);
+        assert!(!accelerator.unit_state(1)?.matrix_loaded);
+        assert!(accelerator.compute_matrix_vector(&vector).is_err());
+
+        // 統計・健全性・キャッシュを残した状態から全体をリセット
+        accelerator.enable_result_cache(4, None);
+        accelerator.enable_shadow_compute(0.0);
+        accelerator.warmup()?;
+        for _ in 0..20 {
+            accelerator.compute_core.record_result(3, false);
+        }
+        assert!(accelerator.unit_health(3)?.blacklisted);
+        let fixed = FpgaValue::from_f32(100.0, QFormat::new(23, 8)?);
+        accelerator.compute_core.get_unit(0)?.load_vector(vec![fixed; MATRIX_SIZE])?;
+        accelerator.compute_core.execute_on(0, ComputeOperation::Scale { factor: 1000.0 })?;
+        assert!(accelerator.saturations() > 0);
+
+        accelerator.prepare_matrix(&matrix)?;
+        accelerator.compute_matrix_vector(&vector)?;
+        assert!(accelerator.shadow_error_stats().operations > 0);
+        accelerator.reset(None)?;
+        let map = accelerator.memory_map()?;
+        assert!(map.units.iter().all(|u| !u.matrix_loaded && !u.vector_loaded));
+        assert_eq!(map.shared_memory, vec![false; 4]);
+        assert_eq!(accelerator.num_available_units(), 4);
+        assert!((0..4).all(|id| accelerator.unit_health(id).is_ok_and(|h| h == UnitHealth::default())));
+        assert!(accelerator.unit_profiles().is_empty());
+        assert_eq!(accelerator.saturations(), 0);
+        assert_eq!(accelerator.shadow_error_stats(), ShadowErrorStats::default());
+        assert!(accelerator.shadow_mismatches().is_empty());
+
+        // 結果キャッシュも空になり、同じ計算はデバイスで再実行される
+        accelerator.prepare_matrix(&matrix)?;
+        accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        Ok(())
+    }
+
+    #[test]
+    fn test_slice_and_concat() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter)?;
+
+        let a: Vec<FpgaValue> = (0..VECTOR_SIZE).map(|i| FpgaValue::Float(i as f32)).collect();
+        let b = vec![FpgaValue::Float(-1.0); VECTOR_SIZE];
+        accelerator.compute_core.get_unit(0)?.load_vector(a)?;
+        accelerator.compute_core.get_unit(1)?.load_vector(b)?;
+
+        let sliced = accelerator.slice(0, 4..8, 2)?;
+        let values: Vec<f32> = sliced.data().iter().map(|x| x.as_f32()).collect();
+        assert_eq!(&values[..4], &[4.0, 5.0, 6.0, 7.0]);
+        assert!(values[4..].iter().all(|&x| x == 0.0));
+
+        let joined = accelerator.concat((0, 3), (1, 2), 3)?;
+        let values: Vec<f32> = joined.data().iter().map(|x| x.as_f32()).collect();
+        assert_eq!(&values[..5], &[0.0, 1.0, 2.0, -1.0, -1.0]);
+
+        assert!(accelerator.concat((0, 10), (1, 10), 3).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fused_activation() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+
+        let matrix = Matrix::from_f32(&vec![vec![-1.0; 16]; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        let result = accelerator.compute_matrix_vector_with_activation(
+            &vector,
+            Some(Activation::ReLU)
+        )?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 0.0));
+        assert!(accelerator.shadow_mismatches().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mlp_inference() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let hidden = MlpLayer::new(
+            Matrix::from_f32(&vec![vec![0.5; 32]; 16], &converter)?,
+            Some(Vector::from_f32(&[-1.0; 16], &converter)?),
+            Some(Activation::ReLU),
+        )?;
+        let output = MlpLayer::new(
+            Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?,
+            None,
+            None,
+        )?;
+        accelerator.prepare_mlp(vec![hidden, output])?;
+
+        // hidden: relu(0.5 * 32 - 1) = 15, output: 16 * 15 = 240
+        let input = Vector::from_f32(&[1.0; 32], &converter)?;
+        let result = accelerator.infer(&input)?;
+        assert_eq!(result.len(), 16);
+        assert!(result.data().iter().all(|x| x.as_f32() == 240.0));
+
+        let mismatched = MlpLayer::new(
+            Matrix::from_f32(&vec![vec![1.0; 32]; 16], &converter)?,
+            None,
+            None,
+        )?;
+        assert!(accelerator.prepare_mlp(vec![mismatched.clone(), mismatched]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mlp_residual_connection() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // y = relu(W x) + x
+        let block = MlpLayer::new(
+            Matrix::from_f32(&vec![vec![0.25; 16]; 16], &converter)?,
+            None,
+            Some(Activation::ReLU),
+        )?.residual_from(MLP_INPUT);
+        accelerator.prepare_mlp(vec![block])?;
+
+        let input = Vector::from_f32(&[1.0; 16], &converter)?;
+        let result = accelerator.infer(&input)?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 5.0));
+
+        let dangling = MlpLayer::new(
+            Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?,
+            None,
+            None,
+        )?.residual_from("missing");
+        assert!(accelerator.prepare_mlp(vec![dangling]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_sparse_mask() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+
+        // 2x2ブロックのうち左上以外を枝刈り
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 32]; 32], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 32], &converter)?;
+        accelerator.prepare_matrix_masked(&matrix, &[false, true, true, true])?;
+
+        let result = accelerator.compute_matrix_vector(&vector)?;
+        assert!(result.data()[..16].iter().all(|x| x.as_f32() == 16.0));
+        assert!(result.data()[16..].iter().all(|x| x.as_f32() == 0.0));
+        assert!(accelerator.shadow_mismatches().is_empty());
+
+        let stats = accelerator.sparsity_stats();
+        assert_eq!((stats.total_blocks, stats.pruned_blocks), (4, 3));
+        assert_eq!(stats.compute_saved(), 0.75);
+
+        assert!(accelerator.prepare_matrix_masked(&matrix, &[true]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blacklisted_unit_excluded() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        for _ in 0..4 {
+            accelerator.compute_core.record_result(0, false);
+        }
+        assert_eq!(accelerator.num_available_units(), 3);
+
+        // 切り離されたユニット0にはブロックを割り当てず、乗算結果は健全性に記録される
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 64]; 16], &converter)?)?;
+        let result = accelerator.compute_matrix_vector(&Vector::from_f32(&[1.0; 64], &converter)?)?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 64.0));
+        assert_eq!(accelerator.unit_health(0)?.successes, 0);
+        assert!((1..4).all(|id| accelerator.unit_health(id).unwrap().successes > 0));
+        assert!(accelerator.slice(1, 0..4, 0).is_err());
+
+        // 既知解検査に合格すれば復帰する
+        assert_eq!(accelerator.probe_units(), vec![0]);
+        let result = accelerator.compute_matrix_vector(&Vector::from_f32(&[1.0; 64], &converter)?)?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 64.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_fallback_policy() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        let matrix = Matrix::from_f32(&vec![vec![0.5; 16]; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        let device = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+
+        accelerator.set_fallback_policy(FallbackPolicy::Always);
+        let host = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Host));
+        assert_eq!(accelerator.host_fallbacks(), 1);
+
+        for (d, h) in device.data().iter().zip(host.data()) {
+            assert_eq!(d.as_f32(), h.as_f32());
+        }
+
+        // 入力の誤りはホストでも解決できないため代替実行しない
+        accelerator.set_fallback_policy(FallbackPolicy::OnError);
+        let wrong = Vector::from_f32(&[1.0; 32], &converter)?;
+        assert!(accelerator.compute_matrix_vector(&wrong).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_warmup() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?)?;
+
+        let profiles = accelerator.warmup()?;
+        assert_eq!(profiles.len(), 4);
+        assert!(profiles.iter().all(|p| p.passed));
+        assert_eq!(accelerator.unit_profiles(), profiles.as_slice());
+
+        // ウォームアップ後は行列の再準備が必要
+        assert_eq!(accelerator.matrix_shape(), None);
+
+        // 実行に失敗するユニットがあっても残りを検証し、後始末を行う
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        accelerator.compute_core.get_unit(1)?.set_failing(true);
+        let profiles = accelerator.warmup()?;
+        assert_eq!(profiles.iter().map(|p| p.passed).collect::<Vec<_>>(), vec![true, false, true, true]);
+        assert_eq!(accelerator.unit_health(1)?.failures, 1);
+        assert_eq!(accelerator.matrix_shape(), None);
+        assert!(accelerator.resident.iter().all(Option::is_none));
+
+        // 再準備した行列は失敗したユニットの古い割り当てに頼らず計算される
+        accelerator.compute_core.get_unit(1)?.set_failing(false);
+        accelerator.prepare_matrix(&matrix)?;
+        let result = accelerator.compute_matrix_vector(&Vector::from_f32(&[1.0; 16], &converter)?)?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 16.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_wide_vector() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+
+        // 列ブロック数（8）がユニット数（2）を超えるためチャンクに分けて計算される
+        let matrix = Matrix::from_f32(&vec![vec![0.5; 128]; 16], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 128], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        let result = accelerator.compute_matrix_vector_with_activation(
+            &vector,
+            Some(Activation::HardTanh)
+        )?;
+        assert_eq!(result.len(), 16);
+        assert!(result.data().iter().all(|x| x.as_f32() == 1.0));
+        assert!(accelerator.shadow_mismatches().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_unit_vector_operation() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+
+        // 5ブロック分（端数あり）を2ユニットで3波に分けて処理
+        let values: Vec<f32> = (0..70).map(|i| i as f32 - 35.0).collect();
+        let vector = Vector::from_f32(&values, &converter)?;
+
+        let relu = accelerator.compute_vector_operation(&vector, None, ComputeOperation::VectorReLU)?;
+        assert_eq!(relu.len(), values.len());
+        for (x, expected) in relu.data().iter().zip(&values) {
+            assert_eq!(x.as_f32(), expected.max(0.0));
+        }
+
+        let scaled = accelerator.compute_vector_operation(&vector, None, ComputeOperation::Scale { factor: 0.5 })?;
+        assert_eq!(scaled.data()[69].as_f32(), 17.0);
+
+        // 第2オペランドは各ブロックに対応する部分がV1へロードされる
+        let other: Vec<f32> = (0..70).map(|i| i as f32 * 2.0).collect();
+        let other = Vector::from_f32(&other, &converter)?;
+        let sum = accelerator.compute_vector_operation(&vector, Some(&other), ComputeOperation::VectorAdd)?;
+        for (i, x) in sum.data().iter().enumerate() {
+            assert_eq!(x.as_f32(), i as f32 * 3.0 - 35.0);
+        }
+        let diff = accelerator.compute_vector_operation(&vector, Some(&other), ComputeOperation::VectorSub)?;
+        let product = accelerator.compute_vector_operation(&vector, Some(&other), ComputeOperation::VectorMul)?;
+        for (i, (d, p)) in diff.data().iter().zip(product.data()).enumerate() {
+            assert_eq!(d.as_f32(), -(i as f32) - 35.0);
+            assert_eq!(p.as_f32(), (i as f32 - 35.0) * i as f32 * 2.0);
+        }
+        assert!(accelerator.compute_vector_operation(&vector, None, ComputeOperation::VectorAdd).is_err());
+        assert!(accelerator.compute_vector_operation(&vector, None, ComputeOperation::VectorMul).is_err());
+        assert!(accelerator
+            .compute_vector_operation(&vector, Some(&other), ComputeOperation::VectorReLU)
+            .is_err());
+        let short = Vector::from_f32(&[1.0; 16], &converter)?;
+        assert!(accelerator
+            .compute_vector_operation(&vector, Some(&short), ComputeOperation::VectorAdd)
+            .is_err());
+
+        assert!(accelerator
+            .compute_vector_operation(&vector, None, ComputeOperation::MatrixVectorMultiply)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_prepared_matrix() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 16]; 32], &converter)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+
+        // 2行目のブロック内の2行だけを更新
+        let rows = Matrix::from_f32(&vec![vec![2.0; 16]; 2], &converter)?;
+        accelerator.update_prepared_matrix(20..22, &rows)?;
+
+        let result = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(result.data()[19].as_f32(), 16.0);
+        assert_eq!(result.data()[20].as_f32(), 32.0);
+        assert!(accelerator.shadow_mismatches().is_empty());
+
+        assert!(accelerator.update_prepared_matrix(31..33, &rows).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_paced_compute() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?)?;
+
+        let vectors = (0..5).map(|_| Vector::from_f32(&[1.0; 16], &converter).unwrap());
+        let mut results = 0;
+        let report = accelerator.compute_paced(vectors, 200.0, |_| results += 1)?;
+
+        assert_eq!((report.operations, results), (5, 5));
+        // 4間隔分（20ms）以上かかり、目標レートを大きく超えない
+        assert!(report.elapsed >= Duration::from_millis(20));
+        assert!(report.achieved_hz() <= 250.0);
+
+        assert!(accelerator.compute_paced(Vec::new(), 0.0, |_| ()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lstm_step() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // 重みゼロ、セル候補ゲートのバイアスのみ1.0: i=f=o=0.5, g=tanh(1)
+        let w = Matrix::from_f32(&vec![vec![0.0; 16]; 64], &converter)?;
+        let u = Matrix::from_f32(&vec![vec![0.0; 16]; 64], &converter)?;
+        let bias: Vec<f32> = (0..64).map(|k| if (32..48).contains(&k) { 1.0 } else { 0.0 }).collect();
+        accelerator.prepare_recurrent(
+            RecurrentKind::Lstm,
+            &w,
+            &u,
+            Some(Vector::from_f32(&bias, &converter)?),
+            None,
+        )?;
+
+        let x = Vector::from_f32(&[1.0; 16], &converter)?;
+        let c1 = 0.5 * 1.0f32.tanh();
+        let h1 = accelerator.recurrent_step(&x)?;
+        assert!((h1.data()[0].as_f32() - 0.5 * c1.tanh()).abs() < 1e-5);
+
+        // 状態ユニットは他の計算に使われない
+        let identity: Vec<Vec<f32>> = (0..16)
+            .map(|i| (0..32).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&identity, &converter)?)?;
+        let other = Vector::from_f32(&[2.0; 32], &converter)?;
+        assert_eq!(accelerator.compute_matrix_vector(&other)?.data()[0].as_f32(), 2.0);
+
+        // セル状態はステップ間で保持される
+        let c2 = 0.5 * c1 + c1;
+        let h2 = accelerator.recurrent_step(&x)?;
+        assert!((h2.data()[0].as_f32() - 0.5 * c2.tanh()).abs() < 1e-5);
+
+        accelerator.reset_recurrent_state()?;
+        let h = accelerator.recurrent_step(&x)?;
+        assert!((h.data()[0].as_f32() - h1.data()[0].as_f32()).abs() < 1e-6);
+
+        // WとUは列方向に結合され、ゼロのブロックを持たない
+        assert_eq!(accelerator.matrix_shape(), Some((64, 32)));
+
+        // 状態の保持に3ユニット、乗算に1ユニット以上が必要
+        let mut small = FpgaAccelerator::new(3, converter.clone())?;
+        assert!(small.prepare_recurrent(RecurrentKind::Lstm, &w, &u, None, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gru_step() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+
+        // (隠れサイズ, 入力サイズ, ユニット数)。隠れサイズ20は2ブロック分の状態ユニットを使う
+        for (n, m, units) in [(16, 16, 4), (20, 12, 8)] {
+            let mut accelerator = FpgaAccelerator::new(units, converter.clone())?;
+            let weights = |cols: usize, scale: f32| -> Vec<Vec<f32>> {
+                (0..3 * n)
+                    .map(|i| (0..cols).map(|j| ((i * 7 + j * 3) % 11) as f32 * scale - 0.25).collect())
+                    .collect()
+            };
+            let (w, u) = (weights(m, 0.05), weights(n, 0.03));
+            let b_ih: Vec<f32> = (0..3 * n).map(|k| (k % 5) as f32 * 0.1).collect();
+            let b_hh: Vec<f32> = (0..3 * n).map(|k| (k % 3) as f32 * -0.1).collect();
+            accelerator.prepare_recurrent(
+                RecurrentKind::Gru,
+                &Matrix::from_f32(&w, &converter)?,
+                &Matrix::from_f32(&u, &converter)?,
+                Some(Vector::from_f32(&b_ih, &converter)?),
+                Some(Vector::from_f32(&b_hh, &converter)?),
+            )?;
+
+            // ホストでのGRU（PyTorchと同じ式）と比較
+            let x: Vec<f32> = (0..m).map(|j| j as f32 * 0.1 - 0.5).collect();
+            let sigmoid = |v: f32| 1.0 / (1.0 + (-v).exp());
+            let dot = |row: &[f32], v: &[f32]| row.iter().zip(v).map(|(a, b)| a * b).sum::<f32>();
+            let mut h = vec![0.0f32; n];
+            for _ in 0..2 {
+                let result = accelerator.recurrent_step(&Vector::from_f32(&x, &converter)?)?;
+                let wx: Vec<f32> = (0..3 * n).map(|k| dot(&w[k], &x) + b_ih[k]).collect();
+                let uh: Vec<f32> = (0..3 * n).map(|k| dot(&u[k], &h) + b_hh[k]).collect();
+                h = (0..n)
+                    .map(|k| {
+                        let r = sigmoid(wx[k] + uh[k]);
+                        let z = sigmoid(wx[n + k] + uh[n + k]);
+                        let candidate = (wx[2 * n + k] + r * uh[2 * n + k]).tanh();
+                        (1.0 - z) * candidate + z * h[k]
+                    })
+                    .collect();
+                assert_eq!(result.len(), n);
+                for (d, e) in result.data().iter().zip(&h) {
+                    assert!((d.as_f32() - e).abs() < 1e-4);
+                }
+            }
+
+            // 新規候補ゲートのみWxとUhを別の行で求め、各グループはブロック境界まで埋める
+            let (hidden_width, input_width) = (n.div_ceil(16) * 16, m.div_ceil(16) * 16);
+            assert_eq!(accelerator.matrix_shape(), Some((4 * hidden_width, input_width + hidden_width)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_builder() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let data: Vec<Vec<f32>> = (0..20)
+            .map(|i| (0..20).map(|j| ((i * 3 + j) % 7) as f32 * 0.1 - 0.3).collect())
+            .collect();
+        let bias: Vec<f32> = (0..20).map(|i| i as f32 * 0.05 - 0.5).collect();
+        let x: Vec<f32> = (0..20).map(|j| (j % 5) as f32 - 2.0).collect();
+        let expected: Vec<f32> = data.iter()
+            .zip(&bias)
+            .map(|(row, b)| (row.iter().zip(&x).map(|(w, v)| w * v).sum::<f32>() + b).max(0.0))
+            .collect();
+
+        // バイアスと活性化は行ブロックごとに結果を保持するユニット上で適用される
+        let weight = Matrix::from_f32(&data, &converter)?;
+        let op = OperationBuilder::matmul(weight.clone())
+            .bias(Vector::from_f32(&bias, &converter)?)
+            .activation(Activation::ReLU)
+            .build()?;
+        let input = Vector::from_f32(&x, &converter)?;
+        let result = accelerator.execute(&op, &input)?.unwrap();
+        for (r, e) in result.data().iter().zip(&expected) {
+            assert!((r.as_f32() - e).abs() < 1e-5);
+        }
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+
+        // 出力先のユニットのV0に結果を残す
+        let op = OperationBuilder::matmul(Matrix::from_f32(&data[..16], &converter)?)
+            .bias(Vector::from_f32(&bias[..16], &converter)?)
+            .activation(Activation::ReLU)
+            .output(OutputTarget::Unit(2))
+            .build()?;
+        assert!(accelerator.execute(&op, &input)?.is_none());
+        let resident: Vec<f32> = accelerator.compute_core.unit(2)?.vector().unwrap()
+            .iter()
+            .map(FpgaValue::as_f32)
+            .collect();
+        for (r, e) in resident.iter().zip(&expected[..16]) {
+            assert!((r - e).abs() < 1e-5);
+        }
+
+        // 形状・係数・出力先の誤りはbuild時に検出される
+        let short = Vector::from_f32(&bias[..8], &converter)?;
+        assert!(OperationBuilder::matmul(weight.clone()).bias(short).build().is_err());
+        assert!(OperationBuilder::matmul(weight.clone())
+            .activation(Activation::ClippedReLU { max: f32::NAN })
+            .build()
+            .is_err());
+        assert!(OperationBuilder::matmul(weight.clone()).output(OutputTarget::Unit(0)).build().is_err());
+
+        // 実行時は入力長と出力先ユニットを検査する
+        let op = OperationBuilder::matmul(weight).output(OutputTarget::Host).build()?;
+        assert!(accelerator.execute(&op, &Vector::from_f32(&x[..16], &converter)?).is_err());
+        let op = OperationBuilder::matmul(Matrix::from_f32(&data[..16], &converter)?)
+            .output(OutputTarget::Unit(9))
+            .build()?;
+        assert!(accelerator.execute(&op, &input).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_k() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        // 行iの出力はi（2つの行ブロックにまたがる）
+        let matrix_data: Vec<Vec<f32>> = (0..32)
+            .map(|i| vec![i as f32 / 16.0; 16])
+            .collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&matrix_data, &converter)?)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+
+        let top = accelerator.compute_top_k(&vector, None, 3)?;
+        assert_eq!(top, vec![(31, 31.0), (30, 30.0), (29, 29.0)]);
+        assert_eq!(accelerator.compute_argmax(&vector, None)?, (31, 31.0));
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        assert!(accelerator.compute_top_k(&vector, None, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_k_unaligned_rows() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        for _ in 0..4 {
+            accelerator.compute_core.record_result(0, false);
+        }
+
+        // 行iの出力は-(i + 1)。最終行ブロックのゼロ埋め行（値0）は候補にならない
+        let matrix_data: Vec<Vec<f32>> = (0..20)
+            .map(|i| vec![-(i as f32 + 1.0) / 16.0; 16])
+            .collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&matrix_data, &converter)?)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+
+        let top = accelerator.compute_top_k(&vector, None, 2)?;
+        assert_eq!(top, vec![(0, -1.0), (1, -2.0)]);
+        assert_eq!(accelerator.unit_health(0)?.successes, 0);
+
+        // kが行数を超えれば全行を返す
+        let all = accelerator.compute_top_k(&vector, None, usize::MAX)?;
+        assert_eq!(all.len(), 20);
+        assert_eq!(all[19], (19, -20.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_stats() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+
+        // 端数ブロックを含む3ブロックを2ユニットで集計
+        let values: Vec<f32> = (0..40).map(|i| i as f32).collect();
+        let stats = accelerator.stats(&Vector::from_f32(&values, &converter)?)?;
+
+        let mean = values.iter().sum::<f32>() / 40.0;
+        let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / 40.0;
+        assert_eq!(stats.count, 40);
+        assert!((stats.mean - mean).abs() < 1e-4);
+        assert!((stats.variance() - variance).abs() < 1e-2);
+        assert_eq!((stats.min, stats.max), (0.0, 39.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_broadcast_scalar() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter)?;
+
+        accelerator.broadcast_scalar(0.25)?;
+        for id in 0..4 {
+            assert!(accelerator.unit_state(id)?.vector_loaded);
+        }
+        // 同じ値を利用してユニット間で演算できる
+        let combined = accelerator.concat((0, 8), (1, 8), 2)?;
+        assert!(combined.data().iter().all(|x| x.as_f32() == 0.25));
+
+        assert!(accelerator.broadcast_scalar(f32::INFINITY).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_result_cache() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_result_cache(4, None);
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?)?;
+
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+        let first = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        let second = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Cache));
+        assert_eq!(first.data()[0].as_f32(), second.data()[0].as_f32());
+
+        // 活性化が異なれば別のエントリ
+        accelerator.compute_matrix_vector_with_activation(&vector, Some(Activation::ReLU))?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+
+        let stats = accelerator.result_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+
+        // 失敗した呼び出しの後は未実行
+        assert!(accelerator.compute_matrix_vector(&Vector::from_f32(&[1.0; 8], &converter)?).is_err());
+        assert_eq!(accelerator.last_execution_target(), None);
+
+        accelerator.disable_result_cache();
+        assert!(accelerator.result_cache_stats().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_result_cache_settings() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Fixed(QFormat::new(23, 8)?));
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_result_cache(4, None);
+        accelerator.set_deterministic(true);
+
+        // 列ブロックごとの部分和は +200, +200, -200, -200
+        let row: Vec<f32> = (0..64).map(|j| if j < 32 { 12.5 } else { -12.5 }).collect();
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![row; 16], &converter)?)?;
+        let vector = Vector::from_f32(&[1.0; 64], &converter)?;
+
+        accelerator.set_accumulation_mode(AccumulationMode::Wide);
+        let wide = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(wide.data()[0].raw(), Some(0));
+
+        // 累積方式を変えるとキャッシュ済みのWideの結果は使われない
+        accelerator.set_accumulation_mode(AccumulationMode::Narrow);
+        let narrow = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+        assert!((narrow.data()[0].as_f32() + 144.0).abs() < 1e-3);
+
+        // リダクション順序も同様
+        accelerator.set_deterministic(false);
+        accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Device));
+
+        // 元の設定に戻せばキャッシュから返る
+        accelerator.set_accumulation_mode(AccumulationMode::Wide);
+        accelerator.set_deterministic(true);
+        assert_eq!(accelerator.compute_matrix_vector(&vector)?.data()[0].raw(), Some(0));
+        assert_eq!(accelerator.last_execution_target(), Some(ExecutionTarget::Cache));
+        Ok(())
+    }
+
+    #[test]
+    fn test_shadow_sampling() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.enable_shadow_compute(1e-3);
+        accelerator.prepare_matrix(&Matrix::from_f32(&vec![vec![0.5; 16]; 16], &converter)?)?;
+        let vector = Vector::from_f32(&[1.0; 16], &converter)?;
+
+        accelerator.set_shadow_sample_rate(0.0)?;
+        accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.shadow_error_stats().operations, 0);
+
+        accelerator.set_shadow_sample_rate(1.0)?;
+        accelerator.compute_matrix_vector(&vector)?;
+        let stats = accelerator.shadow_error_stats();
+        assert_eq!((stats.operations, stats.elements), (1, 16));
+        assert!(stats.max_abs_error <= 1e-3);
+        assert!(stats.rms_error() >= stats.mean_abs_error() - 1e-9);
+
+        assert!(accelerator.set_shadow_sample_rate(1.5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_expression() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+
+        let values: Vec<f32> = (0..40).map(|i| i as f32 - 20.0).collect();
+        let v = Vector::from_f32(&values, &converter)?;
+        let w = Vector::from_f32(&[1.0; 40], &converter)?;
+
+        let result = accelerator.evaluate(&VectorExpr::new(v).relu().scale(0.5).add_vector(&w))?;
+        for (x, input) in result.data().iter().zip(&values) {
+            assert_eq!(x.as_f32(), input.max(0.0) * 0.5 + 1.0);
+        }
+
+        let short = Vector::from_f32(&[1.0; 8], &converter)?;
+        assert!(accelerator.evaluate(&VectorExpr::new(w).add_vector(&short)).is_err());
+
+        // 固定小数点は形式を保ったまま評価し、範囲外は単独の演算と同じく飽和する
+        let format = QFormat::new(23, 8)?;
+        let converter = DataConverter::new(DataFormat::Fixed(format));
+        let mut accelerator = FpgaAccelerator::new(2, converter.clone())?;
+        let v = Vector::from_f32(&values, &converter)?;
+        let w = Vector::from_f32(&[200.0; 40], &converter)?;
+        let result = accelerator.evaluate(&VectorExpr::new(v).relu().scale(0.5).add_vector(&w))?;
+        for (x, input) in result.data().iter().zip(&values) {
+            assert!(x.format().is_some());
+            assert_eq!(*x, FpgaValue::from_f32(input.max(0.0) * 0.5 + 200.0, format));
+        }
+
+        let before = accelerator.saturations();
+        let result = accelerator.evaluate(&VectorExpr::new(w.clone()).add_vector(&w))?;
+        assert!(result.data().iter().all(|x| *x == FpgaValue::from_wide(i64::MAX, format).0));
+        assert_eq!(accelerator.saturations() - before, 40);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameterized_activation() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let matrix = Matrix::from_f32(&vec![vec![1.0; 16]; 16], &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        let vector = Vector::from_f32(&[0.5; 16], &converter)?;
+
+        // 各要素は8.0なので上限6.0で切り詰められる
+        let result = accelerator.compute_matrix_vector_with_activation(
+            &vector,
+            Some(Activation::ClippedReLU { max: 6.0 })
+        )?;
+        assert!(result.data().iter().all(|x| x.as_f32() == 6.0));
+
+        let invalid = Activation::ClippedReLU { max: -1.0 };
+        assert!(accelerator.compute_matrix_vector_with_activation(&vector, Some(invalid)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_checksum_verification() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.set_block_verification(true);
+
+        let data: Vec<Vec<f32>> = (0..32)
+            .map(|i| (0..32).map(|j| (i * 32 + j) as f32 * 0.01).collect())
+            .collect();
+        let matrix = Matrix::from_f32(&data, &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        assert!(accelerator.checksum_failures().is_empty());
+        assert_eq!(accelerator.matrix_shape(), Some((32, 32)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupted_block_detection() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        accelerator.set_block_verification(true);
+
+        let data: Vec<Vec<f32>> = (0..32)
+            .map(|i| (0..32).map(|j| (i * 32 + j) as f32 * 0.01).collect())
+            .collect();
+        let matrix = Matrix::from_f32(&data, &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        accelerator.verify_resident_blocks()?;
+
+        // ユニット2が保持するブロック2の1要素を書き換える
+        accelerator.compute_core.get_unit(2)?.corrupt_matrix(3, 5, FpgaValue::Float(7.0))?;
+        assert!(accelerator.verify_resident_blocks().is_err());
+        assert_eq!(accelerator.checksum_failures(), &[2]);
+
+        // 検出したブロックは次の計算時にロードし直される
+        let vector = Vector::from_f32(&[1.0; 32], &converter)?;
+        let result = accelerator.compute_matrix_vector(&vector)?;
+        let expected = matrix.multiply_vector(&vector)?;
+        for (a, b) in result.data().iter().zip(expected.data()) {
+            assert!((a.as_f32() - b.as_f32()).abs() < 1e-3);
+        }
+        accelerator.verify_resident_blocks()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile_unit_state() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+        assert!(accelerator.reconcile_unit_state()?.is_empty());
+
+        let data: Vec<Vec<f32>> = (0..32)
+            .map(|i| (0..32).map(|j| ((i * 7 + j) % 11) as f32 * 0.1).collect())
+            .collect();
+        let matrix = Matrix::from_f32(&data, &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        assert!(accelerator.reconcile_unit_state()?.is_empty());
+
+        // 電源断で内容を失ったユニットと、M0が書き換わったユニット
+        accelerator.compute_core.get_unit(1)?.reset()?;
+        accelerator.compute_core.get_unit(2)?.corrupt_matrix(0, 0, FpgaValue::Float(9.0))?;
+        assert_eq!(accelerator.reconcile_unit_state()?, vec![1, 2]);
+        assert!(accelerator.resident[1].is_none() && accelerator.resident[2].is_none());
+
+        // 記録を消したブロックは次の計算時にロードし直される
+        let vector = Vector::from_f32(&[1.0; 32], &converter)?;
+        let result = accelerator.compute_matrix_vector(&vector)?;
+        let expected = matrix.multiply_vector(&vector)?;
+        for (a, b) in result.data().iter().zip(expected.data()) {
+            assert!((a.as_f32() - b.as_f32()).abs() < 1e-3);
+        }
+        assert!(accelerator.reconcile_unit_state()?.is_empty());
+
+        // 再帰セルの状態が失われた場合はゼロから計算し直す
+        let w = Matrix::from_f32(&vec![vec![0.0; 16]; 64], &converter)?;
+        let bias: Vec<f32> = (0..64).map(|k| if (32..48).contains(&k) { 1.0 } else { 0.0 }).collect();
+        accelerator.prepare_recurrent(
+            RecurrentKind::Lstm, &w, &w, Some(Vector::from_f32(&bias, &converter)?), None,
+        )?;
+        let x = Vector::from_f32(&[1.0; 16], &converter)?;
+        let h1 = accelerator.recurrent_step(&x)?;
+        let hidden = accelerator.recurrent.as_ref().unwrap().state[0].hidden;
+        accelerator.compute_core.get_unit(hidden)?.reset()?;
+        assert_eq!(accelerator.reconcile_unit_state()?, vec![hidden]);
+        assert_eq!(accelerator.recurrent_step(&x)?.data(), h1.data());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unaligned_matrix() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let mut accelerator = FpgaAccelerator::new(4, converter.clone())?;
+
+        let data: Vec<Vec<f32>> = (0..20)
+            .map(|i| (0..18).map(|j| ((i + j) % 5) as f32 - 2.0).collect())
+            .collect();
+        let matrix = Matrix::from_f32(&data, &converter)?;
+        accelerator.prepare_matrix(&matrix)?;
+        assert_eq!(accelerator.matrix_shape(), Some((20, 18)));
+
+        // ゼロ埋め行は結果に含まれず、ゼロ埋め部分の積和も行わない
+        let vector = Vector::from_f32(&[1.0; 18], &converter)?;
+        let before = accelerator.issue_stats().macs;
+        let result = accelerator.compute_matrix_vector(&vector)?;
+        assert_eq!(accelerator.issue_stats().macs - before, 20 * 18);
+        let expected = matrix.multiply_vector(&vector)?;
+        assert_eq!(result.len(), 20);
+        for (d, h) in result.data().iter().zip(expected.data()) {
+            assert!((d.as_f32() - h.as_f32()).abs() < 1e-5);
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use crate::types::{FpgaError, Result};
 
 /// FPGAの基本命令セット
+///
+/// RTLに実装されているのは一部の命令のみ（`in_rtl`を参照）。
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
 pub enum FpgaInstruction {
@@ -18,6 +20,13 @@ pub enum FpgaInstruction {
     MatrixVectorMul = 0b00001,
     VectorAdd = 0b00010,
     VectorSub = 0b00011,
+    // V0 *= V1（要素ごとの積）
+    VectorMul = 0b11111,
+    VectorScale = 0b00101,
+
+    // 即値オペランドを伴うデータ移動命令
+    VectorFill = 0b00100,
+    VectorCopy = 0b00110,
 
     // 初期化命令
     ZeroV0 = 0b01110,
@@ -33,6 +42,124 @@ pub enum FpgaInstruction {
     VectorRelu = 0b10100,
     VectorHTanh = 0b10101,
     VectorSquare = 0b10110,
+    VectorSigmoid = 0b11001,
+    VectorTanh = 0b11010,
+    // 設定レジスタの係数による活性化関数（SetParamで事前に設定）
+    VectorParamAct = 0b11101,
+
+    // 活性化関数の係数を設定レジスタにロード（オペランド: 種別, 係数1, 係数2）
+    SetParam = 0b00111,
+
+    // V0の先頭len要素のうち上位k要素の(インデックス, 値)を抽出（オペランド: k, len）
+    VectorTopK = 0b11011,
+    // V0の先頭len要素の件数・平均・偏差平方和・最小・最大（オペランド: len）
+    VectorStats = 0b11100,
+
+    // M0のチェックサムを読み出し（ロード結果の検証用）
+    ChecksumM0 = 0b11110,
+
+    // 同期命令（PushV0で書き込み先ブロックのフラグがセットされる）
+    WaitFlag = 0b10111,
+}
+
+/// 命令が読み書きするユニット内のレジスタ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V0,
+    V1,
+    M0,
+    // 活性化関数の係数を保持する設定レジスタ
+    Param,
+}
+
+impl FpgaInstruction {
+    /// 命令が実行前にロード済みであることを要求するレジスタ
+    pub fn reads(self) -> &'static [Register] {
+        use FpgaInstruction::*;
+        match self {
+            StoreV0 | PushV0 | VectorScale | VectorRelu | VectorHTanh | VectorSquare
+            | VectorSigmoid | VectorTanh | VectorTopK | VectorStats => &[Register::V0],
+            StoreV1 => &[Register::V1],
+            StoreM0 | ChecksumM0 => &[Register::M0],
+            MatrixVectorMul => &[Register::M0, Register::V0],
+            VectorAdd | VectorSub | VectorMul => &[Register::V0, Register::V1],
+            VectorParamAct => &[Register::V0, Register::Param],
+            _ => &[],
+        }
+    }
+
+    /// 命令が書き込むレジスタ
+    pub fn writes(self) -> &'static [Register] {
+        use FpgaInstruction::*;
+        match self {
+            LoadV0 | ZeroV0 | PullV0 | VectorFill | VectorCopy | MatrixVectorMul | VectorAdd
+            | VectorSub | VectorMul | VectorScale | VectorRelu | VectorHTanh | VectorSquare
+            | VectorSigmoid | VectorTanh | VectorParamAct => &[Register::V0],
+            LoadV1 | ZeroV1 | PullV1 => &[Register::V1],
+            LoadM0 | ZeroM0 => &[Register::M0],
+            SetParam => &[Register::Param],
+            _ => &[],
+        }
+    }
+}
+
+impl FpgaInstruction {
+    /// RTL（fpga/mtx_types_pkg.svのop_t）に実装されている命令か
+    ///
+    /// それ以外の命令（即値オペランドを伴う命令・追加の活性化関数・TopK・統計・
+    /// チェックサム・同期命令）はシミュレータ内でのみ実行され、命令チャネルには
+    /// 送られない。即値オペランドもこれらの命令にのみ付くため、デバイスへの
+    /// 命令列には含まれない。
+    pub fn in_rtl(self) -> bool {
+        use FpgaInstruction::*;
+        matches!(
+            self,
+            Nop | LoadV0 | LoadV1 | LoadM0 | StoreV0 | StoreV1 | StoreM0
+                | MatrixVectorMul | VectorAdd | VectorSub | VectorMul
+                | ZeroV0 | ZeroV1 | ZeroM0
+                | PushV0 | PullV1 | PullV0
+                | VectorRelu | VectorHTanh | VectorSquare
+        )
+    }
+
+    // 共有メモリを読み書きする命令（互いの順序を保つ必要がある）
+    fn accesses_shared_memory(self) -> bool {
+        use FpgaInstruction::*;
+        matches!(self, PushV0 | PullV0 | PullV1 | VectorCopy)
+    }
+
+    fn is_sync(self) -> bool {
+        self == FpgaInstruction::WaitFlag
+    }
+}
+
+/// 命令ワード発行時点のレジスタのロード状態
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegisterState {
+    pub v0: bool,
+    pub v1: bool,
+    pub m0: bool,
+    pub param: bool,
+}
+
+impl RegisterState {
+    fn loaded(&self, reg: Register) -> bool {
+        match reg {
+            Register::V0 => self.v0,
+            Register::V1 => self.v1,
+            Register::M0 => self.m0,
+            Register::Param => self.param,
+        }
+    }
+
+    fn set_loaded(&mut self, reg: Register) {
+        match reg {
+            Register::V0 => self.v0 = true,
+            Register::V1 => self.v1 = true,
+            Register::M0 => self.m0 = true,
+            Register::Param => self.param = true,
+        }
+    }
 }
 
 /// VLIW命令ワード（4命令をパック）
@@ -65,6 +192,72 @@ impl VliwInstruction {
         }
     }
 
+    /// 実行順に並べたスロット
+    pub fn slots(&self) -> [FpgaInstruction; 4] {
+        [self.op1, self.op2, self.op3, self.op4]
+    }
+
+    /// 発行前の命令ワードの検証
+    ///
+    /// スロットは先頭から順に実行されるものとして、以下を不正とする。
+    /// - 未ロードのレジスタの読み出し（前のスロットでの書き込みは可）
+    /// - 読み出されないまま同じレジスタへ再度書き込む（前の結果が失われる）
+    /// - PushV0の後に同じ命令ワード内で共有メモリから取得する
+    ///
+    /// 検証に成功した場合は実行後のレジスタ状態を返す。
+    pub fn validate(&self, state: RegisterState) -> Result<RegisterState> {
+        let mut state = state;
+        // 書き込み後にまだ読み出されていないレジスタと書き込んだスロット
+        let mut unread: Vec<(Register, usize)> = Vec::new();
+        let mut pushed = None;
+
+        for (i, inst) in self.slots().into_iter().enumerate() {
+            let slot = i + 1;
+            for &reg in inst.reads() {
+                if !state.loaded(reg) {
+                    return Err(FpgaError::Computation(format!(
+                        "スロット{}: {:?}の実行前に{:?}がロードされていません", slot, inst, reg
+                    )));
+                }
+                unread.retain(|&(r, _)| r != reg);
+            }
+
+            if matches!(inst, FpgaInstruction::PullV0 | FpgaInstruction::PullV1) {
+                if let Some(push_slot) = pushed {
+                    return Err(FpgaError::Computation(format!(
+                        "スロット{}: {:?}がスロット{}のPushV0と同じ命令ワード内で共有メモリを読み出しています",
+                        slot, inst, push_slot
+                    )));
+                }
+            }
+            if inst == FpgaInstruction::PushV0 {
+                pushed = Some(slot);
+            }
+
+            for &reg in inst.writes() {
+                if let Some(&(_, prev)) = unread.iter().find(|&&(r, _)| r == reg) {
+                    return Err(FpgaError::Computation(format!(
+                        "スロット{}: {:?}への書き込みがスロット{}の結果を読み出さずに上書きします",
+                        slot, reg, prev
+                    )));
+                }
+                // 読み書き両方を行う命令は直前の結果を連鎖的に使うため対象外
+                if !inst.reads().contains(&reg) {
+                    unread.push((reg, slot));
+                }
+                state.set_loaded(reg);
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// RTLに実装されていない命令をNOPに置き換えた、デバイスへ送る命令ワード
+    pub fn rtl_only(&self) -> Self {
+        let slot = |inst: FpgaInstruction| if inst.in_rtl() { inst } else { FpgaInstruction::Nop };
+        Self::new(slot(self.op1), slot(self.op2), slot(self.op3), slot(self.op4))
+    }
+
     /// VLIW命令ワードをバイト列にパック
     pub fn pack(&self) -> u32 {
         let op1 = (self.op1 as u32) << 24;
@@ -75,6 +268,85 @@ impl VliwInstruction {
     }
 }
 
+/// 命令列をVLIW命令ワードに詰める
+///
+/// 各命令を、依存する命令より前に出ない範囲で最も前の命令ワードの空きスロットへ
+/// 配置する。スロットは先頭から順に実行されるため依存する命令同士も同じ命令ワードに
+/// 入るが、書き込みの上書きとPushV0の後の共有メモリ取得は次の命令ワードに回す。
+/// 同期命令は前後の命令を越えて移動しない。
+pub fn pack_instructions(instructions: &[FpgaInstruction]) -> Vec<VliwInstruction> {
+    let program: Vec<(FpgaInstruction, Vec<u32>)> = instructions.iter()
+        .map(|&inst| (inst, Vec::new()))
+        .collect();
+    pack_program(&program)
+        .into_iter()
+        .map(|(vliw, _)| vliw)
+        .collect()
+}
+
+/// 即値オペランド付きの命令列をVLIW命令ワードに詰める
+///
+/// 配置はpack_instructionsと同じ。各命令ワードのオペランドは、
+/// その命令ワードに入った命令のオペランドをスロット順に連結したもの。
+pub fn pack_program(program: &[(FpgaInstruction, Vec<u32>)]) -> Vec<(VliwInstruction, Vec<u32>)> {
+    let program: Vec<&(FpgaInstruction, Vec<u32>)> = program.iter()
+        .filter(|(inst, _)| *inst != FpgaInstruction::Nop)
+        .collect();
+    let instructions: Vec<FpgaInstruction> = program.iter().map(|(inst, _)| *inst).collect();
+
+    let mut packets: Vec<(Vec<FpgaInstruction>, Vec<u32>)> = Vec::new();
+    for ((inst, operands), target) in program.iter().zip(place_instructions(&instructions)) {
+        if target == packets.len() {
+            packets.push((Vec::new(), Vec::new()));
+        }
+        packets[target].0.push(*inst);
+        packets[target].1.extend_from_slice(operands);
+    }
+
+    packets.into_iter()
+        .map(|(slots, operands)| {
+            let slot = |i: usize| slots.get(i).copied().unwrap_or(FpgaInstruction::Nop);
+            (VliwInstruction::new(slot(0), slot(1), slot(2), slot(3)), operands)
+        })
+        .collect()
+}
+
+// 各命令の配置先の命令ワード番号（Nopを含まない命令列）
+fn place_instructions(instructions: &[FpgaInstruction]) -> Vec<usize> {
+    let mut fill: Vec<usize> = Vec::new();
+    let mut placed: Vec<usize> = Vec::with_capacity(instructions.len());
+
+    for (i, &inst) in instructions.iter().enumerate() {
+        let mut earliest = 0;
+        for (&prev, &packet) in instructions[..i].iter().zip(&placed) {
+            let overwrites = inst.writes().iter()
+                .any(|reg| prev.writes().contains(reg) && !inst.reads().contains(reg));
+            let pulls_after_push = prev == FpgaInstruction::PushV0
+                && matches!(inst, FpgaInstruction::PullV0 | FpgaInstruction::PullV1);
+            let depends = inst.reads().iter().any(|reg| prev.writes().contains(reg))
+                || inst.writes().iter().any(|reg| prev.reads().contains(reg))
+                || (inst.accesses_shared_memory() && prev.accesses_shared_memory())
+                || inst.is_sync() || prev.is_sync();
+
+            if overwrites || pulls_after_push {
+                earliest = earliest.max(packet + 1);
+            } else if depends {
+                earliest = earliest.max(packet);
+            }
+        }
+
+        let target = (earliest..fill.len())
+            .find(|&p| fill[p] < 4)
+            .unwrap_or(fill.len());
+        if target == fill.len() {
+            fill.push(0);
+        }
+        fill[target] += 1;
+        placed.push(target);
+    }
+    placed
+}
+
 /// ComputeOperationとFPGA命令のマッピング
 impl From<crate::compute::ComputeOperation> for FpgaInstruction {
     fn from(op: crate::compute::ComputeOperation) -> Self {
@@ -82,17 +354,81 @@ impl From<crate::compute::ComputeOperation> for FpgaInstruction {
         match op {
             MatrixVectorMultiply => FpgaInstruction::MatrixVectorMul,
             VectorAdd => FpgaInstruction::VectorAdd,
+            VectorSub => FpgaInstruction::VectorSub,
+            VectorMul => FpgaInstruction::VectorMul,
             VectorReLU => FpgaInstruction::VectorRelu,
+            Fill { .. } => FpgaInstruction::VectorFill,
+            Scale { .. } => FpgaInstruction::VectorScale,
+            CopyRange { .. } => FpgaInstruction::VectorCopy,
+        }
+    }
+}
+
+/// 活性化関数とFPGA命令のマッピング
+impl From<crate::compute::Activation> for FpgaInstruction {
+    fn from(activation: crate::compute::Activation) -> Self {
+        use crate::compute::Activation::*;
+        match activation {
+            ReLU => FpgaInstruction::VectorRelu,
+            HardTanh => FpgaInstruction::VectorHTanh,
+            Sigmoid => FpgaInstruction::VectorSigmoid,
+            Tanh => FpgaInstruction::VectorTanh,
+            ClippedReLU { .. } | Elu { .. } | HardSigmoid { .. } => FpgaInstruction::VectorParamAct,
+        }
+    }
+}
+
+/// 係数付き活性化関数のSetParamオペランドのエンコード
+///
+/// `[種別, 係数1, 係数2]` の3ワードで、係数はf32のビット表現。
+/// SetParamはシミュレータ専用の命令で、デバイスへは送られない。
+/// 係数を持たない活性化関数はNoneを返す。
+pub fn encode_activation_params(activation: &crate::compute::Activation) -> Option<Vec<u32>> {
+    use crate::compute::Activation::*;
+    match *activation {
+        ClippedReLU { max } => Some(vec![0, max.to_bits(), 0]),
+        Elu { alpha } => Some(vec![1, alpha.to_bits(), 0]),
+        HardSigmoid { slope, offset } => Some(vec![2, slope.to_bits(), offset.to_bits()]),
+        ReLU | HardTanh | Sigmoid | Tanh => None,
+    }
+}
+
+/// 命令に付随する即値オペランドのエンコード
+///
+/// オペランドを伴う命令はいずれもRTLに実装されていないシミュレータ専用の
+/// 命令で、エンコード結果はシミュレータ内の命令列でのみ使われる。
+///
+/// スカラー値はf32のビット表現、範囲コピーは
+/// `[source:8][src_offset:8][dst_offset:8][len:8]` の1ワードにパックする。
+pub fn encode_operands(op: &crate::compute::ComputeOperation) -> Result<Vec<u32>> {
+    use crate::compute::ComputeOperation::*;
+    match *op {
+        Fill { value } => Ok(vec![value.to_bits()]),
+        Scale { factor } => Ok(vec![factor.to_bits()]),
+        CopyRange { source, src_offset, dst_offset, len } => {
+            let fields = [source, src_offset, dst_offset, len];
+            if fields.iter().any(|&f| f > u8::MAX as usize) {
+                return Err(FpgaError::Computation(
+                    "CopyRangeのオペランドが8ビットに収まりません".into()
+                ));
+            }
+            Ok(vec![fields.iter().fold(0u32, |word, &f| (word << 8) | f as u32)])
         }
+        MatrixVectorMultiply | VectorAdd | VectorSub | VectorMul | VectorReLU => Ok(Vec::new()),
     }
 }
 
 /// FPGAへの命令発行を担当するトレイト
+///
+/// 実装は命令ワードを検証せずに発行する。ComputeUnitの発行経路は
+/// `VliwInstruction::validate` でレジスタ状態を検証してから呼び出す。
 pub trait InstructionExecutor {
-    /// 単一の命令を実行
-    fn execute_instruction(&mut self, inst: FpgaInstruction) -> Result<()>;
-    
-    /// VLIW命令ワードを実行
+    /// 単一の命令を実行（残りのスロットはNopの命令ワードとして発行）
+    fn execute_instruction(&mut self, inst: FpgaInstruction) -> Result<()> {
+        self.execute_vliw(VliwInstruction::from_single(inst))
+    }
+
+    /// VLIW命令ワードを実行（RTLに実装されている命令のみを含む）
     fn execute_vliw(&mut self, vliw: VliwInstruction) -> Result<()>;
 }
 
@@ -116,13 +452,7 @@ impl FpgaInstructionChannel {
 }
 
 impl InstructionExecutor for FpgaInstructionChannel {
-    fn execute_instruction(&mut self, inst: FpgaInstruction) -> Result<()> {
-        // 単一命令の実行
-        // 実際のFPGAとの通信コードをここに実装
-        Ok(())
-    }
-
-    fn execute_vliw(&mut self, vliw: VliwInstruction) -> Result<()> {
+    fn execute_vliw(&mut self, _vliw: VliwInstruction) -> Result<()> {
         // VLIW命令ワードの実行
         // 実際のFPGAとの通信コードをここに実装
         Ok(())
@@ -134,6 +464,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_vliw_instruction_pack() {
         let vliw = VliwInstruction::new(
             FpgaInstruction::LoadV0,
@@ -156,4 +487,105 @@ mod tests {
         let inst: FpgaInstruction = op.into();
         assert_eq!(inst, FpgaInstruction::MatrixVectorMul);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_operand_encoding() {
+        use crate::compute::ComputeOperation;
+
+        let fill = ComputeOperation::Fill { value: 1.5 };
+        let inst: FpgaInstruction = fill.into();
+        assert_eq!(inst, FpgaInstruction::VectorFill);
+        assert_eq!(encode_operands(&fill).unwrap(), vec![1.5f32.to_bits()]);
+
+        let copy = ComputeOperation::CopyRange { source: 2, src_offset: 4, dst_offset: 8, len: 4 };
+        assert_eq!(encode_operands(&copy).unwrap(), vec![0x02_04_08_04]);
+
+        let too_long = ComputeOperation::CopyRange { source: 0, src_offset: 0, dst_offset: 0, len: 256 };
+        assert!(encode_operands(&too_long).is_err());
+
+        let relu = ComputeOperation::VectorReLU;
+        assert!(encode_operands(&relu).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vliw_validation() {
+        use FpgaInstruction::*;
+
+        let loaded = RegisterState { v0: true, m0: true, ..Default::default() };
+
+        // 行列未ロードでの乗算
+        let mul = VliwInstruction::from_single(MatrixVectorMul);
+        assert!(mul.validate(RegisterState { m0: false, ..loaded }).is_err());
+        assert!(mul.validate(loaded).is_ok());
+
+        // 同じスロット列内で取得したV1は読み出せる
+        let add = VliwInstruction::new(PullV1, VectorAdd, PushV0, Nop);
+        assert!(VliwInstruction::new(VectorAdd, Nop, Nop, Nop).validate(loaded).is_err());
+        assert_eq!(add.validate(loaded).unwrap(), RegisterState { v1: true, ..loaded });
+
+        // 結果を使わずにV0を上書き
+        assert!(VliwInstruction::new(LoadV0, ZeroV0, Nop, Nop).validate(loaded).is_err());
+        // 読み書きを行う命令の連鎖は可
+        assert!(VliwInstruction::new(VectorRelu, VectorScale, Nop, Nop).validate(loaded).is_ok());
+
+        // PushV0の後の取得は不可、取得後のPushV0は可
+        assert!(VliwInstruction::new(PushV0, PullV0, Nop, Nop).validate(loaded).is_err());
+        assert!(VliwInstruction::new(PullV0, MatrixVectorMul, PushV0, Nop).validate(loaded).is_ok());
+    }
+
+    #[test]
+    fn test_instruction_packing() {
+        use FpgaInstruction::*;
+
+        let program = [
+            LoadM0, LoadV0, MatrixVectorMul, VectorRelu,
+            PushV0, PullV1, VectorAdd, Nop,
+            ZeroM0, VectorScale,
+        ];
+        let packets = pack_instructions(&program);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].slots(), [LoadM0, LoadV0, MatrixVectorMul, VectorRelu]);
+        // M0の初期化は乗算より後、PushV0の後の取得は次の命令ワード
+        assert_eq!(packets[1].slots(), [PushV0, ZeroM0, Nop, Nop]);
+        assert_eq!(packets[2].slots(), [PullV1, VectorAdd, VectorScale, Nop]);
+
+        // 詰めた結果は順に発行して検証を通る
+        let mut state = RegisterState::default();
+        for packet in &packets {
+            state = packet.validate(state).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_program_packing_operands() {
+        use FpgaInstruction::*;
+
+        // オペランドは詰めた先の命令ワードにスロット順で付く
+        let program = [
+            (LoadV0, vec![]),
+            (SetParam, vec![0, 6.0f32.to_bits()]),
+            (VectorParamAct, vec![]),
+            (VectorScale, vec![0.5f32.to_bits()]),
+            (StoreV0, vec![]),
+            (VectorFill, vec![1.0f32.to_bits()]),
+        ];
+        let packets = pack_program(&program);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].0.slots(), [LoadV0, SetParam, VectorParamAct, VectorScale]);
+        assert_eq!(packets[0].1, vec![0, 6.0f32.to_bits(), 0.5f32.to_bits()]);
+        assert_eq!(packets[1].0.slots(), [StoreV0, VectorFill, Nop, Nop]);
+        assert_eq!(packets[1].1, vec![1.0f32.to_bits()]);
+    }
+
+    #[test]
+    fn test_rtl_subset() {
+        use FpgaInstruction::*;
+
+        assert!([LoadV0, MatrixVectorMul, VectorSub, VectorMul, PullV0, VectorSquare].iter().all(|i| i.in_rtl()));
+        assert!([VectorFill, SetParam, VectorSigmoid, ChecksumM0, WaitFlag].iter().all(|i| !i.in_rtl()));
+
+        // シミュレータ専用の命令はデバイスへ送る命令ワードから除かれる
+        let vliw = VliwInstruction::new(SetParam, VectorParamAct, PushV0, WaitFlag);
+        assert_eq!(vliw.rtl_only().slots(), [Nop, Nop, PushV0, Nop]);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE, VECTOR_SIZE, DataConverter};
-use std::ops::{Add, Mul};
+use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE, DataConverter};
+use crate::simd::{self, dot};
 
 #[derive(Debug, Clone)]
 pub struct Matrix {
@@ -22,53 +22,101 @@ impl Matrix {
         Ok(Self { data, rows, cols })
     }
 
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn data(&self) -> &[Vec<FpgaValue>] {
+        &self.data
+    }
+
     pub fn from_f32(data: &[Vec<f32>], converter: &DataConverter) -> Result<Self> {
-        let converted = data.iter()
-            .map(|row| row.iter()
-                .map(|&x| converter.convert(x))
-                .collect::<Result<Vec<_>>>())
-            .collect::<Result<Vec<_>>>()?;
-        Self::new(converted)
+        // 三値化の閾値は行列全体で較正する
+        let values: Vec<f32> = data.iter().flatten().copied().collect();
+        let mut converted = converter.convert_all(&values)?.into_iter();
+        let rows = data.iter()
+            .map(|row| converted.by_ref().take(row.len()).collect())
+            .collect();
+        Self::new(rows)
     }
 
     pub fn multiply_vector(&self, vector: &Vector) -> Result<Vector> {
         if self.cols != vector.len() {
-            return Err(FpgaError::Computation("Dimension mismatch".into()));
-        }
-
-        let result = (0..self.rows)
-            .map(|i| {
-                let sum = (0..self.cols)
-                    .map(|j| {
-                        let a = self.data[i][j].as_f32();
-                        let b = vector.data[j].as_f32();
-                        a * b
-                    })
-                    .sum();
-                Ok(FpgaValue::Float(sum))
+            return Err(FpgaError::Dimension("Dimension mismatch".into()));
+        }
+
+        let v: Vec<f32> = vector.data.iter().map(|x| x.as_f32()).collect();
+        let mut row = Vec::with_capacity(self.cols);
+        let result = self.data.iter()
+            .map(|values| {
+                row.clear();
+                row.extend(values.iter().map(|x| x.as_f32()));
+                Ok(FpgaValue::Float(dot(&row, &v)))
             })
             .collect::<Result<Vec<_>>>()?;
 
         Vector::new(result)
     }
 
-    pub fn split_blocks(&self) -> Result<Vec<Matrix>> {
-        if self.rows % MATRIX_SIZE != 0 || self.cols % MATRIX_SIZE != 0 {
-            return Err(FpgaError::Computation("Matrix size must be multiple of block size".into()));
-        }
+    // 行ブロック数・列ブロック数（端数は1ブロックに切り上げ）
+    pub fn block_dims(&self) -> (usize, usize) {
+        (self.rows.div_ceil(MATRIX_SIZE), self.cols.div_ceil(MATRIX_SIZE))
+    }
 
-        let mut blocks = Vec::new();
-        for i in (0..self.rows).step_by(MATRIX_SIZE) {
-            for j in (0..self.cols).step_by(MATRIX_SIZE) {
-                let block_data: Vec<Vec<FpgaValue>> = self.data[i..i + MATRIX_SIZE]
-                    .iter()
-                    .map(|row| row[j..j + MATRIX_SIZE].to_vec())
-                    .collect();
-                blocks.push(Matrix::new(block_data)?);
+    // ブロックの倍数でない行列は端のブロックをゼロで埋めて分割
+    pub fn split_blocks(&self) -> Result<Vec<Matrix>> {
+        let (block_rows, block_cols) = self.block_dims();
+        let mut blocks = Vec::with_capacity(block_rows * block_cols);
+        for i in 0..block_rows {
+            for j in 0..block_cols {
+                blocks.push(self.block(i, j)?);
             }
         }
         Ok(blocks)
     }
+
+    // ブロック位置(行ブロック, 列ブロック)のMATRIX_SIZE×MATRIX_SIZE部分行列
+    //
+    // 行列の範囲外にはみ出す部分はゼロで埋める
+    pub fn block(&self, block_row: usize, block_col: usize) -> Result<Matrix> {
+        let (i, j) = (block_row * MATRIX_SIZE, block_col * MATRIX_SIZE);
+        if i >= self.rows || j >= self.cols {
+            return Err(FpgaError::Dimension("Block index out of range".into()));
+        }
+        let (valid_rows, valid_cols) = self.block_extent(block_row, block_col);
+        // ゼロ埋めは元の要素と同じ表現にする（固定小数点の演算を崩さない）
+        let zero = self.data[i][j].with_value(0.0);
+        let block_data: Vec<Vec<FpgaValue>> = (0..MATRIX_SIZE)
+            .map(|r| {
+                let mut row = Vec::with_capacity(MATRIX_SIZE);
+                if r < valid_rows {
+                    row.extend_from_slice(&self.data[i + r][j..j + valid_cols]);
+                }
+                row.resize(MATRIX_SIZE, zero.clone());
+                row
+            })
+            .collect();
+        Matrix::new(block_data)
+    }
+
+    // ブロック内の有効な(行数, 列数)（残りはゼロ埋め）
+    pub fn block_extent(&self, block_row: usize, block_col: usize) -> (usize, usize) {
+        let valid = |len: usize, start: usize| len.saturating_sub(start).min(MATRIX_SIZE);
+        (valid(self.rows, block_row * MATRIX_SIZE), valid(self.cols, block_col * MATRIX_SIZE))
+    }
+
+    // start行目から行を上書き
+    pub fn set_rows(&mut self, start: usize, rows: &[Vec<FpgaValue>]) -> Result<()> {
+        if start + rows.len() > self.rows || rows.iter().any(|row| row.len() != self.cols) {
+            return Err(FpgaError::Dimension("Row update out of range".into()));
+        }
+        self.data[start..start + rows.len()].clone_from_slice(rows);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -85,31 +133,55 @@ impl Vector {
     }
 
     pub fn from_f32(data: &[f32], converter: &DataConverter) -> Result<Self> {
-        let converted = data.iter()
-            .map(|&x| converter.convert(x))
-            .collect::<Result<Vec<_>>>()?;
-        Self::new(converted)
+        Self::new(converter.convert_all(data)?)
     }
 
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    // 空のベクトルは生成できないため常にfalse
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn data(&self) -> &[FpgaValue] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<FpgaValue> {
+        self.data
+    }
+
     pub fn split(&self, block_size: usize) -> Result<Vec<Vector>> {
-        if self.len() % block_size != 0 {
-            return Err(FpgaError::Computation("Vector size must be multiple of block size".into()));
+        if block_size == 0 || !self.len().is_multiple_of(block_size) {
+            return Err(FpgaError::Dimension("Vector size must be multiple of block size".into()));
+        }
+
+        self.data.chunks(block_size)
+            .map(|chunk| Vector::new(chunk.to_vec()))
+            .collect()
+    }
+
+    // 末尾の端数ブロックをゼロで埋めて分割（ブロックの倍数でない行列との乗算用）
+    pub fn split_padded(&self, block_size: usize) -> Result<Vec<Vector>> {
+        if block_size == 0 {
+            return Err(FpgaError::Dimension("Block size must be positive".into()));
         }
 
         let mut blocks = Vec::new();
         for chunk in self.data.chunks(block_size) {
-            blocks.push(Vector::new(chunk.to_vec())?);
+            let mut block = chunk.to_vec();
+            let zero = block[0].with_value(0.0);
+            block.resize(block_size, zero);
+            blocks.push(Vector::new(block)?);
         }
         Ok(blocks)
     }
 
     pub fn add(&self, other: &Vector) -> Result<Vector> {
         if self.len() != other.len() {
-            return Err(FpgaError::Computation("Vector size mismatch".into()));
+            return Err(FpgaError::Dimension("Vector size mismatch".into()));
         }
 
         let result = self.data.iter()
@@ -121,13 +193,151 @@ impl Vector {
     }
 
     pub fn relu(&self) -> Result<Vector> {
+        let mut values: Vec<f32> = self.data.iter().map(|x| x.as_f32()).collect();
+        simd::clamp_affine(&mut values, 1.0, 0.0, 0.0, f32::INFINITY);
         let result = self.data.iter()
-            .map(|x| FpgaValue::Float(x.as_f32().max(0.0)))
+            .zip(values)
+            .map(|(x, v)| x.with_value(v))
             .collect();
         Vector::new(result)
     }
 }
 
+/// テンソルデータの要素並び順
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Layout {
+    /// 最後の次元が連続（C順）
+    #[default]
+    RowMajor,
+    /// 最初の次元が連続（Fortran順）
+    ColumnMajor,
+}
+
+/// 任意次元のテンソル（形状情報付きの一次元データ）
+///
+/// デバイスへ渡す際はto_matrixで2次元のブロック配置に落とし込む。
+#[derive(Debug, Clone)]
+pub struct Tensor {
+    data: Vec<FpgaValue>,
+    shape: Vec<usize>,
+    layout: Layout,
+}
+
+impl Tensor {
+    pub fn new(data: Vec<FpgaValue>, shape: &[usize], layout: Layout) -> Result<Self> {
+        if shape.is_empty() || shape.contains(&0) {
+            return Err(FpgaError::Dimension(format!("Invalid tensor shape: {:?}", shape)));
+        }
+        let len: usize = shape.iter().product();
+        if len != data.len() {
+            return Err(FpgaError::Dimension(format!(
+                "Shape {:?} requires {} elements, got {}", shape, len, data.len()
+            )));
+        }
+        Ok(Self { data, shape: shape.to_vec(), layout })
+    }
+
+    pub fn from_f32(data: &[f32], shape: &[usize], layout: Layout, converter: &DataConverter) -> Result<Self> {
+        Self::new(converter.convert_all(data)?, shape, layout)
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // 要素数を変えずに形状を変更（要素の並びはそのまま）
+    pub fn reshape(mut self, shape: &[usize]) -> Result<Self> {
+        let len: usize = shape.iter().product();
+        if shape.is_empty() || len != self.data.len() {
+            return Err(FpgaError::Dimension(format!(
+                "Cannot reshape {:?} into {:?}", self.shape, shape
+            )));
+        }
+        self.shape = shape.to_vec();
+        Ok(self)
+    }
+
+    // 行優先順に並べ替えた一次元ベクトル
+    pub fn flatten(&self) -> Result<Vector> {
+        Vector::new(self.row_major_data())
+    }
+
+    /// 先頭からsplit個の次元を行、残りを列として2次元行列に変換
+    ///
+    /// 例えば (batch, channels, length) をsplit=1で変換すると
+    /// batch行 × (channels * length)列になる。
+    pub fn to_matrix(&self, split: usize) -> Result<Matrix> {
+        if split == 0 || split >= self.shape.len() {
+            return Err(FpgaError::Dimension(format!(
+                "Split {} is out of range for rank {}", split, self.shape.len()
+            )));
+        }
+        let cols: usize = self.shape[split..].iter().product();
+        let rows = self.row_major_data()
+            .chunks(cols)
+            .map(|row| row.to_vec())
+            .collect();
+        Matrix::new(rows)
+    }
+
+    // 列優先のデータを行優先の並びに変換
+    fn row_major_data(&self) -> Vec<FpgaValue> {
+        match self.layout {
+            Layout::RowMajor => self.data.clone(),
+            Layout::ColumnMajor => {
+                let rank = self.shape.len();
+                let mut index = vec![0; rank];
+                let mut result = Vec::with_capacity(self.data.len());
+                for _ in 0..self.data.len() {
+                    // 行優先の多次元インデックスから列優先のオフセットを計算
+                    let mut offset = 0;
+                    let mut stride = 1;
+                    for (i, &dim) in index.iter().zip(&self.shape) {
+                        offset += i * stride;
+                        stride *= dim;
+                    }
+                    result.push(self.data[offset].clone());
+
+                    for axis in (0..rank).rev() {
+                        index[axis] += 1;
+                        if index[axis] < self.shape[axis] {
+                            break;
+                        }
+                        index[axis] = 0;
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+impl From<Vector> for Tensor {
+    fn from(vector: Vector) -> Self {
+        let shape = vec![vector.len()];
+        Self { data: vector.data, shape, layout: Layout::RowMajor }
+    }
+}
+
+impl From<Matrix> for Tensor {
+    fn from(matrix: Matrix) -> Self {
+        let shape = vec![matrix.rows, matrix.cols];
+        Self { data: matrix.data.into_iter().flatten().collect(), shape, layout: Layout::RowMajor }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,4 +376,63 @@ mod tests {
         assert_eq!(relu.data[0].as_f32(), 1.0);
         assert_eq!(relu.data[1].as_f32(), 0.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_simd_dot_matches_scalar() {
+        // レーン数の倍数でない長さで端数処理も確認
+        let a: Vec<f32> = (0..37).map(|i| i as f32 * 0.25).collect();
+        let b: Vec<f32> = (0..37).map(|i| 1.0 - i as f32 * 0.125).collect();
+
+        let scalar: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+        assert!((dot(&a, &b) - scalar).abs() < 1e-3);
+        assert_eq!(dot(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_tensor_lowering() {
+        let converter = DataConverter::new(DataFormat::Full);
+        let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+
+        // (batch=2, channels=3, length=2) をバッチ行の行列へ
+        let tensor = Tensor::from_f32(&values, &[2, 3, 2], Layout::RowMajor, &converter).unwrap();
+        let matrix = tensor.to_matrix(1).unwrap();
+        assert_eq!((matrix.rows(), matrix.cols()), (2, 6));
+        assert_eq!(matrix.data()[1][0].as_f32(), 6.0);
+
+        // 列優先の(2, 3)は行優先で [0, 2, 4, 1, 3, 5]
+        let tensor = Tensor::from_f32(&values[..6], &[2, 3], Layout::ColumnMajor, &converter).unwrap();
+        let flat: Vec<f32> = tensor.flatten().unwrap().data().iter().map(|x| x.as_f32()).collect();
+        assert_eq!(flat, vec![0.0, 2.0, 4.0, 1.0, 3.0, 5.0]);
+
+        assert!(tensor.clone().reshape(&[3, 2]).is_ok());
+        assert!(tensor.reshape(&[4, 2]).is_err());
+    }
+
+    #[test]
+    fn test_padded_blocks() -> Result<()> {
+        let converter = DataConverter::new(DataFormat::Full);
+        let data: Vec<Vec<f32>> = (0..20).map(|i| vec![i as f32 + 1.0; 18]).collect();
+        let matrix = Matrix::from_f32(&data, &converter)?;
+
+        assert_eq!(matrix.block_dims(), (2, 2));
+        assert_eq!(matrix.block_extent(1, 0), (4, MATRIX_SIZE));
+        assert_eq!(matrix.block_extent(1, 1), (4, 2));
+
+        // 端のブロックは有効範囲外がゼロ
+        let blocks = matrix.split_blocks()?;
+        assert_eq!(blocks.len(), 4);
+        let edge = &blocks[3];
+        assert_eq!(edge.data()[3][1].as_f32(), 20.0);
+        assert_eq!(edge.data()[3][2].as_f32(), 0.0);
+        assert_eq!(edge.data()[4][0].as_f32(), 0.0);
+
+        // splitは端数を受け付けず、split_paddedは末尾をゼロで埋める
+        let vector = Vector::from_f32(&[1.0; 18], &converter)?;
+        assert!(vector.split(MATRIX_SIZE).is_err());
+        let parts = vector.split_padded(MATRIX_SIZE)?;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].data()[1].as_f32(), 1.0);
+        assert_eq!(parts[1].data()[2].as_f32(), 0.0);
+        Ok(())
+    }
+}
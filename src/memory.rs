@@ -1,9 +1,12 @@
 use crate::types::{FpgaError, Result, FpgaValue, MATRIX_SIZE, VECTOR_SIZE};
-use std::sync::{Arc, Mutex};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct MemoryBlock {
     data: Vec<FpgaValue>,
+    // Wide累積中の拡張精度の部分和（PushV0で飽和前の値も書き出す）
+    wide: Option<Vec<i64>>,
     block_id: usize,
     is_valid: bool,
 }
@@ -12,13 +15,19 @@ impl MemoryBlock {
     pub fn new(block_id: usize) -> Self {
         Self {
             data: vec![FpgaValue::Float(0.0); VECTOR_SIZE],
+            wide: None,
             block_id,
             is_valid: false,
         }
     }
 
     pub fn write(&mut self, data: Vec<FpgaValue>) -> Result<()> {
-        if data.len() != VECTOR_SIZE {
+        self.write_wide(data, None)
+    }
+
+    // 飽和前の拡張精度の部分和とともに書き込む
+    pub fn write_wide(&mut self, data: Vec<FpgaValue>, wide: Option<Vec<i64>>) -> Result<()> {
+        if data.len() != VECTOR_SIZE || wide.as_ref().is_some_and(|w| w.len() != VECTOR_SIZE) {
             return Err(FpgaError::Memory(format!(
                 "Invalid vector size: expected {}, got {}",
                 VECTOR_SIZE,
@@ -26,20 +35,42 @@ impl MemoryBlock {
             )));
         }
         self.data = data;
+        self.wide = wide;
         self.is_valid = true;
         Ok(())
     }
 
+    pub fn block_id(&self) -> usize {
+        self.block_id
+    }
+
+    // 内容を破棄して未初期化状態に戻す
+    pub fn invalidate(&mut self) {
+        self.is_valid = false;
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.is_valid
+    }
+
     pub fn read(&self) -> Result<&[FpgaValue]> {
         if !self.is_valid {
             return Err(FpgaError::Memory("Block not initialized".into()));
         }
         Ok(&self.data)
     }
+
+    // 拡張精度の部分和（書き込まれていなければNone）
+    pub fn read_wide(&self) -> Result<Option<&[i64]>> {
+        self.read()?;
+        Ok(self.wide.as_deref())
+    }
 }
 
 pub struct SharedMemory {
     blocks: Vec<Mutex<MemoryBlock>>,
+    // 各ブロックへの書き込み完了通知（WaitFlag命令に対応）
+    ready: Vec<Condvar>,
 }
 
 impl SharedMemory {
@@ -47,33 +78,175 @@ impl SharedMemory {
         let blocks = (0..num_blocks)
             .map(|id| Mutex::new(MemoryBlock::new(id)))
             .collect();
-        Self { blocks }
+        let ready = (0..num_blocks).map(|_| Condvar::new()).collect();
+        Self { blocks, ready }
     }
 
     pub fn write_block(&self, block_id: usize, data: Vec<FpgaValue>) -> Result<()> {
+        self.write_block_wide(block_id, data, None)
+    }
+
+    // 飽和前の拡張精度の部分和とともに書き込む（Wide累積のリダクション用）
+    pub fn write_block_wide(&self, block_id: usize, data: Vec<FpgaValue>, wide: Option<Vec<i64>>) -> Result<()> {
         self.blocks
             .get(block_id)
             .ok_or_else(|| FpgaError::Memory("Invalid block ID".into()))?
             .lock()
             .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))?
-            .write(data)
+            .write_wide(data, wide)?;
+        self.ready[block_id].notify_all();
+        Ok(())
+    }
+
+    // ブロックに有効なデータが書き込まれるまで待機して取り出す（取り出し後は無効化）
+    //
+    // リダクションで同じブロックを二度読んだり、書き込み前の古い値を
+    // 読んだりしないよう、読み出しと無効化を一つのロック内で行う。
+    pub fn pop_block(&self, block_id: usize, timeout: Duration) -> Result<Vec<FpgaValue>> {
+        Ok(self.pop_block_wide(block_id, timeout)?.0)
+    }
+
+    // pop_blockと同じく取り出し、拡張精度の部分和があれば併せて返す
+    pub fn pop_block_wide(
+        &self,
+        block_id: usize,
+        timeout: Duration,
+    ) -> Result<(Vec<FpgaValue>, Option<Vec<i64>>)> {
+        let block = self.blocks
+            .get(block_id)
+            .ok_or_else(|| FpgaError::Memory("Invalid block ID".into()))?
+            .lock()
+            .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))?;
+
+        let (mut block, result) = self.ready[block_id]
+            .wait_timeout_while(block, timeout, |b| !b.is_valid())
+            .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))?;
+        if result.timed_out() {
+            return Err(FpgaError::Memory(format!(
+                "Timed out waiting for block {}", block_id
+            )));
+        }
+
+        let data = block.read()?.to_vec();
+        let wide = block.read_wide()?.map(<[i64]>::to_vec);
+        block.invalidate();
+        Ok((data, wide))
+    }
+
+    pub fn clear_block(&self, block_id: usize) -> Result<()> {
+        self.blocks
+            .get(block_id)
+            .ok_or_else(|| FpgaError::Memory("Invalid block ID".into()))?
+            .lock()
+            .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))?
+            .invalidate();
+        Ok(())
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // 各ブロックに有効なデータが書き込まれているか
+    pub fn block_states(&self) -> Result<Vec<bool>> {
+        self.blocks
+            .iter()
+            .map(|block| {
+                block.lock()
+                    .map(|b| b.is_valid())
+                    .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))
+            })
+            .collect()
     }
 
     pub fn read_block(&self, block_id: usize) -> Result<Vec<FpgaValue>> {
+        Ok(self.read_block_wide(block_id)?.0)
+    }
+
+    pub fn read_block_wide(&self, block_id: usize) -> Result<(Vec<FpgaValue>, Option<Vec<i64>>)> {
         let block = self.blocks
             .get(block_id)
             .ok_or_else(|| FpgaError::Memory("Invalid block ID".into()))?
             .lock()
             .map_err(|_| FpgaError::Memory("Lock acquisition failed".into()))?;
-        Ok(block.read()?.to_vec())
+        Ok((block.read()?.to_vec(), block.read_wide()?.map(<[i64]>::to_vec)))
     }
 }
 
+/// ベクトルプールの利用統計
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PoolStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl PoolStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// 中間結果用ベクトルバッファのプール
 #[derive(Debug)]
+pub struct VectorPool {
+    buffers: Vec<Vec<FpgaValue>>,
+    max_buffers: usize,
+    stats: PoolStats,
+}
+
+impl VectorPool {
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            buffers: Vec::with_capacity(max_buffers),
+            max_buffers,
+            stats: PoolStats::default(),
+        }
+    }
+
+    // 空のバッファを取得（プールが空なら新規確保）
+    pub fn acquire(&mut self) -> Vec<FpgaValue> {
+        match self.buffers.pop() {
+            Some(buffer) => {
+                self.stats.hits += 1;
+                buffer
+            }
+            None => {
+                self.stats.misses += 1;
+                Vec::with_capacity(VECTOR_SIZE)
+            }
+        }
+    }
+
+    // バッファを返却（上限を超える分は破棄）
+    pub fn release(&mut self, mut buffer: Vec<FpgaValue>) {
+        if self.buffers.len() < self.max_buffers {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    // 再利用待ちのバッファ数
+    pub fn available(&self) -> usize {
+        self.buffers.len()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MatrixBlock {
     data: Vec<Vec<FpgaValue>>,
     row_offset: usize,
     col_offset: usize,
+    // 有効な(行数, 列数)（行列の端のブロックでは残りがゼロ埋め）
+    extent: (usize, usize),
 }
 
 impl MatrixBlock {
@@ -85,9 +258,23 @@ impl MatrixBlock {
             data,
             row_offset,
             col_offset,
+            extent: (MATRIX_SIZE, MATRIX_SIZE),
         })
     }
 
+    /// 有効範囲を設定（範囲外の行・列は乗算で読み飛ばされる）
+    pub fn with_extent(mut self, rows: usize, cols: usize) -> Result<Self> {
+        if rows == 0 || cols == 0 || rows > MATRIX_SIZE || cols > MATRIX_SIZE {
+            return Err(FpgaError::Memory(format!("Invalid block extent: {}x{}", rows, cols)));
+        }
+        self.extent = (rows, cols);
+        Ok(self)
+    }
+
+    pub fn get_extent(&self) -> (usize, usize) {
+        self.extent
+    }
+
     pub fn get_data(&self) -> &[Vec<FpgaValue>] {
         &self.data
     }
@@ -95,12 +282,29 @@ impl MatrixBlock {
     pub fn get_offsets(&self) -> (usize, usize) {
         (self.row_offset, self.col_offset)
     }
+
+    /// ChecksumM0命令と同じ方式で計算したブロックのチェックサム
+    pub fn checksum(&self) -> u64 {
+        checksum_values(self.data.iter().flatten())
+    }
+}
+
+/// 行列ブロックのチェックサム（Fletcher方式）
+///
+/// 単純和に加えて位置で重み付けした和を持つため、値の変化だけでなく
+/// 要素の入れ替わりも検出できる。
+pub fn checksum_values<'a>(values: impl IntoIterator<Item = &'a FpgaValue>) -> u64 {
+    let (sum, weighted) = values.into_iter().fold((0u32, 0u32), |(sum, weighted), value| {
+        let sum = sum.wrapping_add(value.as_f32().to_bits());
+        (sum, weighted.wrapping_add(sum))
+    });
+    ((weighted as u64) << 32) | sum as u64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::DataConverter;
+    use std::sync::Arc;
 
     #[test]
     fn test_memory_block_operations() {
@@ -119,4 +323,62 @@ mod tests {
         assert!(mem.write_block(0, data.clone()).is_ok());
         assert_eq!(mem.read_block(0).unwrap().len(), VECTOR_SIZE);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_vector_pool_recycling() {
+        let mut pool = VectorPool::new(1);
+
+        let mut buffer = pool.acquire();
+        buffer.extend(vec![FpgaValue::Float(1.0); VECTOR_SIZE]);
+        pool.release(buffer);
+
+        let buffer = pool.acquire();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= VECTOR_SIZE);
+
+        let stats = pool.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_pop_block_waits_for_writer() {
+        let mem = Arc::new(SharedMemory::new(2));
+        let writer = {
+            let mem = Arc::clone(&mem);
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(10));
+                mem.write_block(1, vec![FpgaValue::Float(2.0); VECTOR_SIZE]).unwrap();
+            })
+        };
+
+        let data = mem.pop_block(1, Duration::from_secs(1)).unwrap();
+        assert_eq!(data.len(), VECTOR_SIZE);
+        writer.join().unwrap();
+
+        // 取り出し済みのブロックは再度読めない
+        assert!(mem.pop_block(1, Duration::from_millis(1)).is_err());
+    }
+
+    #[test]
+    fn test_block_checksum() -> Result<()> {
+        let rows = |values: &[f32]| -> Vec<Vec<FpgaValue>> {
+            values.chunks(MATRIX_SIZE)
+                .map(|row| row.iter().map(|&x| FpgaValue::Float(x)).collect())
+                .collect()
+        };
+        let values: Vec<f32> = (0..MATRIX_SIZE * MATRIX_SIZE).map(|i| i as f32).collect();
+        let block = MatrixBlock::new(rows(&values), 0, 0)?;
+
+        // 1要素の変化と要素の入れ替わりを検出
+        let mut changed = values.clone();
+        changed[37] += 1.0;
+        assert_ne!(MatrixBlock::new(rows(&changed), 0, 0)?.checksum(), block.checksum());
+
+        let mut swapped = values.clone();
+        swapped.swap(0, 32);
+        assert_ne!(MatrixBlock::new(rows(&swapped), 0, 0)?.checksum(), block.checksum());
+        Ok(())
+    }
+}
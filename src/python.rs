@@ -0,0 +1,651 @@
+use pyo3::prelude::*;
+use pyo3::create_exception;
+use pyo3::types::PyDict;
+use numpy::{PyArray1, PyArray2, PyArrayDyn, ToPyArray};
+use numpy::ndarray::{Array1, Array2};
+use std::sync::{Arc, Mutex};
+
+use crate::types::{DataConverter, DataFormat, QFormat, FpgaError, TrinaryThreshold};
+use crate::math::{Layout, Matrix, Tensor, Vector};
+use crate::device::{FallbackPolicy, FpgaAccelerator};
+use crate::{compute, types};
+
+// Python側の例外階層
+create_exception!(fpga_accelerator, FpgaAcceleratorError, pyo3::exceptions::PyException);
+create_exception!(fpga_accelerator, ConfigurationError, FpgaAcceleratorError);
+create_exception!(fpga_accelerator, ConversionError, FpgaAcceleratorError);
+create_exception!(fpga_accelerator, DimensionError, FpgaAcceleratorError);
+create_exception!(fpga_accelerator, FormatMismatchError, FpgaAcceleratorError);
+create_exception!(fpga_accelerator, HardwareError, FpgaAcceleratorError);
+
+impl From<FpgaError> for PyErr {
+    fn from(e: FpgaError) -> Self {
+        match e {
+            FpgaError::Configuration(_) => ConfigurationError::new_err(e.to_string()),
+            FpgaError::TypeConversion(_) => ConversionError::new_err(e.to_string()),
+            FpgaError::Dimension(_) => DimensionError::new_err(e.to_string()),
+            FpgaError::FormatMismatch(..) => FormatMismatchError::new_err(e.to_string()),
+            FpgaError::Computation(_) | FpgaError::Memory(_) => {
+                HardwareError::new_err(e.to_string())
+            }
+        }
+    }
+}
+
+// アクセラレータとレイヤーで共有するデバイスハンドル
+//
+// アクセラレータの状態（準備済み行列・キャッシュ等）はこのMutexで保護し、
+// 呼び出し単位で直列化する。各ユニットはComputeCore内で個別にロックされ、
+// set_concurrent_units(True)では1回の呼び出し内の乗算がユニットごとに並行する。
+// ロック待ち・処理中はGILを解放するので、他のPythonスレッドは進行できる。close()後はNone。
+struct SharedDevice(Mutex<Option<FpgaAccelerator>>);
+
+impl SharedDevice {
+    fn new(device: FpgaAccelerator) -> Self {
+        Self(Mutex::new(Some(device)))
+    }
+
+    // GILを解放した状態でデバイスをロックし処理を実行
+    fn with<T, F>(&self, py: Python, f: F) -> PyResult<T>
+    where
+        T: Send,
+        F: FnOnce(&mut FpgaAccelerator) -> types::Result<T> + Send,
+    {
+        py.allow_threads(|| {
+            let mut guard = self.0.lock()
+                .map_err(|_| HardwareError::new_err("デバイスのロック取得に失敗しました"))?;
+            let device = guard.as_mut()
+                .ok_or_else(|| FpgaAcceleratorError::new_err("アクセラレータは既に解放されています"))?;
+            Ok(f(device)?)
+        })
+    }
+
+    fn close(&self) {
+        if let Ok(mut guard) = self.0.lock() {
+            *guard = None;
+        }
+    }
+}
+
+#[pyclass]
+struct PyFpgaAccelerator {
+    // FpgaLinearと共有するデバイスハンドル
+    inner: Arc<SharedDevice>,
+    q_format: QFormat,
+    converter: DataConverter,
+}
+
+#[pymethods]
+impl PyFpgaAccelerator {
+    #[new]
+    fn new(q: Option<u8>, int: Option<u8>) -> PyResult<Self> {
+        // デフォルト値：Q23.8
+        let q_format = QFormat::new(
+            q.unwrap_or(23),
+            int.unwrap_or(8)
+        )?;
+
+        let converter = DataConverter::new(DataFormat::Fixed(q_format));
+
+        Ok(Self {
+            inner: Arc::new(SharedDevice::new(FpgaAccelerator::new(4, converter.clone())?)),
+            q_format,
+            converter,
+        })
+    }
+
+    #[getter]
+    fn get_format(&self) -> PyResult<(u8, u8)> {
+        Ok((self.q_format.q, self.q_format.int))
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>
+    ) -> bool {
+        self.close();
+        false
+    }
+
+    // ユニットと共有メモリを解放（以降の呼び出しはエラー）
+    fn close(&self) {
+        self.inner.close();
+    }
+
+    // ユニットをリセット（unit_id省略時は全体を初期化）
+    #[pyo3(text_signature = "(self, unit_id=None)")]
+    fn reset(&self, py: Python, unit_id: Option<usize>) -> PyResult<()> {
+        self.inner.with(py, |device| device.reset(unit_id))
+    }
+
+    // 結果キャッシュの有効化（size=0で無効化、ttlは秒）
+    #[pyo3(text_signature = "(self, size, ttl=None)")]
+    fn set_result_cache(&self, py: Python, size: usize, ttl: Option<f64>) -> PyResult<()> {
+        let ttl = match ttl {
+            Some(secs) if !(secs > 0.0 && secs.is_finite()) => {
+                return Err(ConfigurationError::new_err(format!("不正なTTLです: {}", secs)))
+            }
+            Some(secs) => Some(std::time::Duration::from_secs_f64(secs)),
+            None => None,
+        };
+        self.inner.with(py, |device| {
+            if size == 0 {
+                device.disable_result_cache();
+            } else {
+                device.enable_result_cache(size, ttl);
+            }
+            Ok(())
+        })
+    }
+
+    // ホスト代替実行ポリシーの設定（'never'、'on_error'、'always'）
+    #[pyo3(text_signature = "(self, policy)")]
+    fn set_fallback_policy(&self, py: Python, policy: &str) -> PyResult<()> {
+        let policy = match policy {
+            "never" => FallbackPolicy::Never,
+            "on_error" => FallbackPolicy::OnError,
+            "always" => FallbackPolicy::Always,
+            other => {
+                return Err(ConfigurationError::new_err(format!(
+                    "不明な代替実行ポリシーです: {}", other
+                )))
+            }
+        };
+        self.inner.with(py, |device| {
+            device.set_fallback_policy(policy);
+            Ok(())
+        })
+    }
+
+    // 行列ロード後のブロックチェックサム検証の切り替え
+    #[pyo3(text_signature = "(self, enabled)")]
+    fn set_block_verification(&self, py: Python, enabled: bool) -> PyResult<()> {
+        self.inner.with(py, |device| {
+            device.set_block_verification(enabled);
+            Ok(())
+        })
+    }
+
+    // 行列ブロックの乗算をユニットごとのスレッドで並行実行するかの切り替え
+    #[pyo3(text_signature = "(self, enabled)")]
+    fn set_concurrent_units(&self, py: Python, enabled: bool) -> PyResult<()> {
+        self.inner.with(py, |device| {
+            device.set_concurrent_units(enabled);
+            Ok(())
+        })
+    }
+
+    // ユニットのレジスタ内容をnumpy配列の辞書で返す（未ロードのレジスタはNone）
+    #[cfg(feature = "debug")]
+    #[pyo3(text_signature = "(self, unit_id)")]
+    fn register_snapshot(&self, py: Python, unit_id: usize) -> PyResult<PyObject> {
+        let snapshot = self.inner.with(py, |device| device.register_snapshot(unit_id))?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("unit_id", snapshot.unit_id)?;
+        dict.set_item("v0", snapshot.v0.map(|v| v.to_pyarray(py).to_owned()))?;
+        dict.set_item("v1", snapshot.v1.map(|v| v.to_pyarray(py).to_owned()))?;
+        let m0 = match snapshot.m0 {
+            Some(rows) => Some(PyArray2::from_vec2(py, &rows)?.to_owned()),
+            None => None,
+        };
+        dict.set_item("m0", m0)?;
+        Ok(dict.to_object(py))
+    }
+
+    // 切り離し中のユニットを再検査し、復帰したユニットのIDを返す
+    fn probe_units(&self, py: Python) -> PyResult<Vec<usize>> {
+        self.inner.with(py, |device| Ok(device.probe_units()))
+    }
+
+    // 全ユニットで既知解計算を行い、ユニットごとの性能と検証結果を返す
+    // （準備済み行列は破棄される）
+    fn warmup(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let profiles = self.inner.with(py, |device| device.warmup())?;
+        profiles.iter()
+            .map(|profile| {
+                let dict = PyDict::new(py);
+                dict.set_item("id", profile.id)?;
+                dict.set_item("latency_us", profile.latency.as_secs_f64() * 1e6)?;
+                dict.set_item("throughput", profile.throughput())?;
+                dict.set_item("passed", profile.passed)?;
+                Ok(dict.into())
+            })
+            .collect()
+    }
+
+    // アクセラレータ全体の状態を辞書で返す
+    fn status(&self, py: Python) -> PyResult<PyObject> {
+        let (num_units, available, matrix_shape, reduction_order, mismatches, cache, pool, sparsity, fallbacks, saturations, results, units) =
+            self.inner.with(py, |device| {
+                let units = (0..device.num_units())
+                    .map(|id| device.unit_state(id))
+                    .collect::<types::Result<Vec<_>>>()?;
+                Ok((
+                    device.num_units(),
+                    device.num_available_units(),
+                    device.matrix_shape(),
+                    format!("{:?}", device.reduction_order()),
+                    device.shadow_mismatches().len(),
+                    device.matrix_cache_stats(),
+                    device.vector_pool_stats(),
+                    device.sparsity_stats(),
+                    device.host_fallbacks(),
+                    device.saturations(),
+                    device.result_cache_stats(),
+                    units,
+                ))
+            })?;
+
+        let status = PyDict::new(py);
+        status.set_item("num_units", num_units)?;
+        status.set_item("available_units", available)?;
+        status.set_item("matrix_shape", matrix_shape)?;
+        status.set_item("reduction_order", reduction_order)?;
+        status.set_item("shadow_mismatches", mismatches)?;
+        status.set_item("matrix_cache_hits", cache.hits)?;
+        status.set_item("matrix_cache_misses", cache.misses)?;
+        status.set_item("matrix_cache_evictions", cache.evictions)?;
+        status.set_item("vector_pool_hit_rate", pool.hit_rate())?;
+        status.set_item("pruned_blocks", sparsity.pruned_blocks)?;
+        status.set_item("compute_saved", sparsity.compute_saved())?;
+        status.set_item("host_fallbacks", fallbacks)?;
+        status.set_item("saturations", saturations)?;
+        status.set_item("result_cache_hit_rate", results.map(|stats| stats.hit_rate()))?;
+
+        let units = units.iter()
+            .map(|state| unit_state_dict(py, state))
+            .collect::<PyResult<Vec<_>>>()?;
+        status.set_item("units", units)?;
+        Ok(status.to_object(py))
+    }
+
+    // ユニット・共有メモリ上のデータ配置を辞書で返す
+    fn memory_map(&self, py: Python) -> PyResult<PyObject> {
+        let map = self.inner.with(py, |device| device.memory_map())?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("matrix_shape", map.matrix_shape)?;
+        let units = map.units.iter()
+            .map(|state| unit_state_dict(py, state))
+            .collect::<PyResult<Vec<_>>>()?;
+        dict.set_item("units", units)?;
+        dict.set_item("shared_memory", map.shared_memory)?;
+        dict.set_item("pooled_buffers", map.pooled_buffers)?;
+        Ok(dict.to_object(py))
+    }
+
+    // 指定ユニットの状態を辞書で返す
+    #[pyo3(text_signature = "(self, unit_id)")]
+    fn unit_state(&self, py: Python, unit_id: usize) -> PyResult<PyObject> {
+        let state = self.inner.with(py, |device| device.unit_state(unit_id))?;
+        unit_state_dict(py, &state)
+    }
+
+    // 任意次元の配列を先頭split次元×残りの次元の行列として準備
+    // （例: (batch, channels, length) はsplit=1でbatch × channels*length）
+    #[pyo3(text_signature = "(self, array, split=1)")]
+    fn prepare_tensor(&self, py: Python, array: &PyArrayDyn<f32>, split: Option<usize>) -> PyResult<()> {
+        let array = array.readonly();
+        let array = array.as_array();
+        // as_arrayの反復は配列のメモリ順によらず論理的な行優先順
+        let values: Vec<f32> = array.iter().copied().collect();
+
+        let tensor = Tensor::from_f32(&values, array.shape(), Layout::RowMajor, &self.converter)?;
+        let matrix = tensor.to_matrix(split.unwrap_or(1))?;
+        self.inner.with(py, |device| device.prepare_matrix(&matrix))
+    }
+
+    #[pyo3(text_signature = "(self, matrix, block_mask=None)")]
+    fn prepare_matrix(
+        &self,
+        py: Python,
+        matrix: &PyArray2<f32>,
+        block_mask: Option<&PyArray2<bool>>
+    ) -> PyResult<()> {
+        let matrix_data: Vec<Vec<f32>> = matrix
+            .readonly()
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect();
+
+        let fpga_matrix = Matrix::from_f32(&matrix_data, &self.converter)?;
+
+        // ブロックマスクは (行ブロック数, 列ブロック数) の真偽値配列
+        match block_mask {
+            Some(mask) => {
+                let mask: Vec<bool> = mask.readonly().as_array().iter().copied().collect();
+                self.inner.with(py, |device| device.prepare_matrix_masked(&fpga_matrix, &mask))
+            }
+            None => self.inner.with(py, |device| device.prepare_matrix(&fpga_matrix)),
+        }
+    }
+
+    // 準備済み行列のstart行目からの行を更新（影響する行ブロックのみ再転送）
+    #[pyo3(text_signature = "(self, start, rows)")]
+    fn update_prepared_matrix(&self, py: Python, start: usize, rows: &PyArray2<f32>) -> PyResult<()> {
+        let rows_data: Vec<Vec<f32>> = rows
+            .readonly()
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect();
+        let values = Matrix::from_f32(&rows_data, &self.converter)?;
+        let range = start..start + values.rows();
+
+        self.inner.with(py, |device| device.update_prepared_matrix(range, &values))
+    }
+
+    #[pyo3(text_signature = "(self, vector, activation=None)")]
+    fn compute_matrix_vector(
+        &self,
+        py: Python,
+        vector: &PyArray1<f32>,
+        activation: Option<&str>
+    ) -> PyResult<Py<PyArray1<f32>>> {
+        let vector_data: Vec<f32> = vector.readonly().as_slice()?.to_vec();
+
+        let fpga_vector = Vector::from_f32(&vector_data, &self.converter)?;
+        let activation = parse_activation(activation)?;
+
+        let result = self.inner.with(py, |device| {
+            device.compute_matrix_vector_with_activation(&fpga_vector, activation)
+        })?;
+
+        let numpy_result: Vec<f32> = result.data().iter().map(|x| x.as_f32()).collect();
+        Ok(numpy_result.to_pyarray(py).to_owned())
+    }
+
+    // 行列ベクトル乗算の上位k要素を[(index, value), ...]の降順で返す
+    #[pyo3(text_signature = "(self, vector, k, activation=None)")]
+    fn compute_top_k(
+        &self,
+        py: Python,
+        vector: &PyArray1<f32>,
+        k: usize,
+        activation: Option<&str>
+    ) -> PyResult<Vec<(usize, f32)>> {
+        let vector_data: Vec<f32> = vector.readonly().as_slice()?.to_vec();
+        let fpga_vector = Vector::from_f32(&vector_data, &self.converter)?;
+        let activation = parse_activation(activation)?;
+
+        self.inner.with(py, |device| device.compute_top_k(&fpga_vector, activation, k))
+    }
+
+    // ベクトルの平均・分散・最小・最大を辞書で返す
+    #[pyo3(text_signature = "(self, vector)")]
+    fn stats(&self, py: Python, vector: &PyArray1<f32>) -> PyResult<PyObject> {
+        let vector_data: Vec<f32> = vector.readonly().as_slice()?.to_vec();
+        let fpga_vector = Vector::from_f32(&vector_data, &self.converter)?;
+        let stats = self.inner.with(py, |device| device.stats(&fpga_vector))?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("mean", stats.mean)?;
+        dict.set_item("var", stats.variance())?;
+        dict.set_item("min", stats.min)?;
+        dict.set_item("max", stats.max)?;
+        Ok(dict.to_object(py))
+    }
+
+    #[pyo3(text_signature = "(self, vector, operation, value=None, other=None)")]
+    fn compute_vector(
+        &self,
+        py: Python,
+        vector: &PyArray1<f32>,
+        operation: &str,
+        value: Option<f32>,
+        other: Option<&PyArray1<f32>>
+    ) -> PyResult<Py<PyArray1<f32>>> {
+        let vector_data: Vec<f32> = vector.readonly().as_slice()?.to_vec();
+        let fpga_vector = Vector::from_f32(&vector_data, &self.converter)?;
+
+        // fill/scaleはスカラー値が必須
+        let scalar = || value.ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("この演算にはvalueの指定が必要です")
+        });
+        let op = match operation {
+            "relu" => compute::ComputeOperation::VectorReLU,
+            "add" => compute::ComputeOperation::VectorAdd,
+            "fill" => compute::ComputeOperation::fill(scalar()?)?,
+            "scale" => compute::ComputeOperation::scale(scalar()?)?,
+            _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("不正な演算タイプ")),
+        };
+
+        // addの第2オペランド
+        let operand = match other {
+            Some(other) => Some(Vector::from_f32(other.readonly().as_slice()?, &self.converter)?),
+            None => None,
+        };
+
+        let result = self.inner.with(py, |device| {
+            device.compute_vector_operation(&fpga_vector, operand.as_ref(), op)
+        })?;
+
+        let numpy_result: Vec<f32> = result.data().iter().map(|x| x.as_f32()).collect();
+        Ok(numpy_result.to_pyarray(py).to_owned())
+    }
+
+    #[pyo3(text_signature = "(self, matrix, conversion, threshold=None, percentile=None)")]
+    fn convert_matrix(
+        &self,
+        py: Python,
+        matrix: &PyArray2<f32>,
+        conversion: &str,
+        threshold: Option<f32>,
+        percentile: Option<f32>
+    ) -> PyResult<Py<PyArray2<f32>>> {
+        let array = matrix.readonly();
+        let array = array.as_array();
+        let values: Vec<f32> = array.iter().copied().collect();
+
+        let converted: Vec<f32> = match conversion {
+            "trinary" => {
+                // 閾値未指定時は従来通り非ゼロ値をすべて±1に変換
+                let setting = match (threshold, percentile) {
+                    (Some(delta), None) => TrinaryThreshold::Fixed(delta),
+                    (None, Some(p)) => TrinaryThreshold::Percentile(p),
+                    (None, None) => TrinaryThreshold::Fixed(0.0),
+                    _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "thresholdとpercentileは同時に指定できません")),
+                };
+                DataConverter::new(DataFormat::Trinary(Some(setting)))
+                    .convert_all(&values)?
+                    .iter()
+                    .map(|t| t.as_f32())
+                    .collect()
+            }
+            _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("不正な変換タイプ")),
+        };
+
+        let cols = array.ncols();
+        let rows: Vec<Vec<f32>> = converted.chunks(cols.max(1)).map(|row| row.to_vec()).collect();
+        Ok(PyArray2::from_vec2(py, &rows)
+            .map_err(|e| DimensionError::new_err(e.to_string()))?
+            .to_owned())
+    }
+
+    // フォーマット情報の文字列表現を返す
+    fn __str__(&self) -> PyResult<String> {
+        Ok(format!("Q{}.{} 固定小数点形式 FPGA アクセラレータ",
+            self.q_format.q, self.q_format.int))
+    }
+}
+
+// 係数付きの活性化関数は"elu:0.5"や"hard_sigmoid:0.2,0.5"の形式で係数を指定
+// （省略時はclipped_relu: 6.0、elu: 1.0、hard_sigmoid: 0.2,0.5）
+fn parse_activation(name: Option<&str>) -> PyResult<Option<compute::Activation>> {
+    use compute::Activation::*;
+
+    let invalid = || PyErr::new::<pyo3::exceptions::PyValueError, _>("不正な活性化関数");
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let (kind, coefficients) = match name.split_once(':') {
+        Some((kind, params)) => {
+            let coefficients = params.split(',')
+                .map(|c| c.trim().parse::<f32>())
+                .collect::<std::result::Result<Vec<f32>, _>>()
+                .map_err(|_| invalid())?;
+            (kind, coefficients)
+        }
+        None => (name, Vec::new()),
+    };
+
+    let activation = match (kind, coefficients.as_slice()) {
+        ("relu", []) => ReLU,
+        ("hardtanh", []) => HardTanh,
+        ("sigmoid", []) => Sigmoid,
+        ("tanh", []) => Tanh,
+        ("clipped_relu", []) => ClippedReLU { max: 6.0 },
+        ("clipped_relu", &[max]) => ClippedReLU { max },
+        ("elu", []) => Elu { alpha: 1.0 },
+        ("elu", &[alpha]) => Elu { alpha },
+        ("hard_sigmoid", []) => HardSigmoid { slope: 0.2, offset: 0.5 },
+        ("hard_sigmoid", &[slope, offset]) => HardSigmoid { slope, offset },
+        _ => return Err(invalid()),
+    };
+    Ok(Some(activation))
+}
+
+fn unit_state_dict(py: Python, state: &compute::UnitState) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", state.id)?;
+    dict.set_item("matrix_loaded", state.matrix_loaded)?;
+    dict.set_item("vector_loaded", state.vector_loaded)?;
+    dict.set_item("matrix_offset", state.matrix_offset)?;
+    Ok(dict.to_object(py))
+}
+
+// torch.nn.Linear相当の推論用レイヤー（y = activation(Wx + b)）
+//
+// 生成元のアクセラレータのデバイスを共有し、複数のレイヤーが同じユニットを使う。
+// 重みは行列キャッシュに常駐させ、呼び出しのたびに自分の重みへ切り替えてから計算する。
+#[pyclass]
+struct FpgaLinear {
+    inner: Arc<SharedDevice>,
+    weight: Matrix,
+    bias: Option<Vector>,
+    activation: Option<compute::Activation>,
+    converter: DataConverter,
+    in_features: usize,
+    out_features: usize,
+}
+
+#[pymethods]
+impl FpgaLinear {
+    #[new]
+    #[pyo3(text_signature = "(accelerator, weight, bias=None, activation=None)")]
+    fn new(
+        py: Python,
+        accelerator: PyRef<'_, PyFpgaAccelerator>,
+        weight: &PyArray2<f32>,
+        bias: Option<&PyArray1<f32>>,
+        activation: Option<&str>
+    ) -> PyResult<Self> {
+        let converter = accelerator.converter.clone();
+
+        let weight_data: Vec<Vec<f32>> = weight
+            .readonly()
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect();
+        let matrix = Matrix::from_f32(&weight_data, &converter)?;
+        let (out_features, in_features) = (matrix.rows(), matrix.cols());
+
+        let bias = match bias {
+            Some(b) => {
+                let bias_data = b.readonly().as_slice()?.to_vec();
+                if bias_data.len() != out_features {
+                    return Err(DimensionError::new_err(format!(
+                        "バイアスの長さが出力次元と一致しません: {} != {}",
+                        bias_data.len(), out_features
+                    )));
+                }
+                Some(Vector::from_f32(&bias_data, &converter)?)
+            }
+            None => None,
+        };
+
+        let activation = parse_activation(activation)?;
+
+        // 重みを行列キャッシュに載せておく（形状・データ形式の検証も兼ねる）
+        let inner = Arc::clone(&accelerator.inner);
+        inner.with(py, |device| device.prepare_matrix_cached(&matrix))?;
+
+        Ok(Self {
+            inner,
+            weight: matrix,
+            bias,
+            activation,
+            converter,
+            in_features,
+            out_features,
+        })
+    }
+
+    #[getter]
+    fn in_features(&self) -> usize {
+        self.in_features
+    }
+
+    #[getter]
+    fn out_features(&self) -> usize {
+        self.out_features
+    }
+
+    fn __call__(&self, py: Python, x: &PyArray1<f32>) -> PyResult<Py<PyArray1<f32>>> {
+        let input = Vector::from_f32(x.readonly().as_slice()?, &self.converter)?;
+
+        // 重みの切り替えと計算は同じロック内で行い、他のレイヤーの呼び出しと混ざらない
+        let weight = &self.weight;
+        let output = match &self.bias {
+            // バイアスなしなら活性化をデバイス側で融合実行
+            None => {
+                let activation = self.activation;
+                self.inner.with(py, |device| {
+                    device.prepare_matrix_cached(weight)?;
+                    device.compute_matrix_vector_with_activation(&input, activation)
+                })?
+            }
+            Some(bias) => {
+                let output = self.inner.with(py, |device| {
+                    device.prepare_matrix_cached(weight)?;
+                    device.compute_matrix_vector(&input)
+                })?;
+                let output = output.add(bias)?;
+                let values = output.data().iter()
+                    .map(|x| self.activation.map_or(x.as_f32(), |a| a.apply(x.as_f32())))
+                    .collect::<Vec<f32>>();
+                Vector::from_f32(&values, &self.converter)?
+            }
+        };
+
+        let numpy_result: Vec<f32> = output.data().iter().map(|x| x.as_f32()).collect();
+        Ok(numpy_result.to_pyarray(py).to_owned())
+    }
+}
+
+#[pymodule]
+fn fpga_accelerator(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyFpgaAccelerator>()?;
+    m.add_class::<FpgaLinear>()?;
+    m.add("FpgaAcceleratorError", py.get_type::<FpgaAcceleratorError>())?;
+    m.add("ConfigurationError", py.get_type::<ConfigurationError>())?;
+    m.add("ConversionError", py.get_type::<ConversionError>())?;
+    m.add("DimensionError", py.get_type::<DimensionError>())?;
+    m.add("FormatMismatchError", py.get_type::<FormatMismatchError>())?;
+    m.add("HardwareError", py.get_type::<HardwareError>())?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+// ホスト参照計算のSIMDカーネル
+//
+// モック・シミュレータの行列ベクトル積と活性化関数はここのカーネルで計算する。
+// 使用する実装は初回呼び出し時にCPUから判定し、AVX2+FMAが使えればそれを有効に
+// して再コンパイルしたカーネル、使えなければコンパイル時のターゲット機能による
+// `wide`のカーネルを使う。SIMD命令を持たないターゲットではスカラー実装に戻る。
+
+use std::sync::OnceLock;
+
+// SIMD演算のレーン数
+const LANES: usize = 8;
+
+/// ホスト参照計算で使うSIMD実装
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    /// スカラー演算
+    Scalar,
+    /// `wide`の8レーン演算（コンパイル時のターゲット機能）
+    Wide,
+    /// 実行時に検出したAVX2+FMA
+    Avx2,
+}
+
+static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+
+/// このCPUで使うSIMD実装（初回呼び出し時に判定）
+pub fn simd_level() -> SimdLevel {
+    *LEVEL.get_or_init(detect)
+}
+
+fn detect() -> SimdLevel {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        return SimdLevel::Avx2;
+    }
+    if cfg!(any(target_feature = "sse2", target_feature = "neon", target_feature = "simd128")) {
+        SimdLevel::Wide
+    } else {
+        SimdLevel::Scalar
+    }
+}
+
+// 実装ごとにカーネルを呼び分ける（Avx2はx86以外では選ばれない）
+macro_rules! dispatch {
+    ($level:expr, $scalar:expr, $kernel:ident($($arg:expr),*)) => {
+        match $level {
+            SimdLevel::Scalar => $scalar,
+            // SAFETY: Avx2はAVX2とFMAを実行時に検出した場合のみ選ばれる
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { avx2::$kernel($($arg),*) },
+            _ => kernels::$kernel($($arg),*),
+        }
+    };
+}
+
+/// f32の内積
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    dot_with(simd_level(), a, b)
+}
+
+fn dot_with(level: SimdLevel, a: &[f32], b: &[f32]) -> f32 {
+    dispatch!(level, a.iter().zip(b).map(|(x, y)| x * y).sum(), dot(a, b))
+}
+
+/// 固定小数点の生の値（i32）の内積（シフト前の積和をi128で返す）
+pub(crate) fn dot_fixed(a: &[i32], b: &[i32]) -> i128 {
+    dot_fixed_with(simd_level(), a, b)
+}
+
+fn dot_fixed_with(level: SimdLevel, a: &[i32], b: &[i32]) -> i128 {
+    dispatch!(
+        level,
+        a.iter().zip(b).map(|(&x, &y)| x as i128 * y as i128).sum(),
+        dot_fixed(a, b)
+    )
+}
+
+/// 各要素を `(slope * x + offset)` として `[lo, hi]` に制限（区分線形の活性化関数）
+pub(crate) fn clamp_affine(values: &mut [f32], slope: f32, offset: f32, lo: f32, hi: f32) {
+    clamp_affine_with(simd_level(), values, slope, offset, lo, hi)
+}
+
+fn clamp_affine_with(level: SimdLevel, values: &mut [f32], slope: f32, offset: f32, lo: f32, hi: f32) {
+    dispatch!(
+        level,
+        values.iter_mut().for_each(|x| *x = (slope * *x + offset).max(lo).min(hi)),
+        clamp_affine(values, slope, offset, lo, hi)
+    )
+}
+
+// 各実装で共通のカーネル本体（呼び出し側の関数にインライン展開される）
+mod kernels {
+    use super::LANES;
+    use wide::f32x8;
+
+    // 8レーンずつ積和し、端数はスカラーで処理
+    #[inline(always)]
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut acc = f32x8::ZERO;
+        let mut a_chunks = a.chunks_exact(LANES);
+        let mut b_chunks = b.chunks_exact(LANES);
+        for (ca, cb) in (&mut a_chunks).zip(&mut b_chunks) {
+            let va = f32x8::from(<[f32; LANES]>::try_from(ca).unwrap());
+            let vb = f32x8::from(<[f32; LANES]>::try_from(cb).unwrap());
+            acc = va.mul_add(vb, acc);
+        }
+
+        let tail: f32 = a_chunks.remainder().iter()
+            .zip(b_chunks.remainder())
+            .map(|(x, y)| x * y)
+            .sum();
+        acc.reduce_add() + tail
+    }
+
+    // i32同士の積（i64）を上位・下位32ビットに分けてレーンごとにi64で累積し、
+    // 最後にi128へ合成する（要素数2^31未満では桁あふれしない）
+    #[inline(always)]
+    pub fn dot_fixed(a: &[i32], b: &[i32]) -> i128 {
+        let mut hi = [0i64; LANES];
+        let mut lo = [0i64; LANES];
+        let mut a_chunks = a.chunks_exact(LANES);
+        let mut b_chunks = b.chunks_exact(LANES);
+        for (ca, cb) in (&mut a_chunks).zip(&mut b_chunks) {
+            for k in 0..LANES {
+                let product = ca[k] as i64 * cb[k] as i64;
+                hi[k] += product >> 32;
+                lo[k] += product & 0xffff_ffff;
+            }
+        }
+
+        let tail: i128 = a_chunks.remainder().iter()
+            .zip(b_chunks.remainder())
+            .map(|(&x, &y)| x as i128 * y as i128)
+            .sum();
+        let hi: i128 = hi.iter().map(|&x| x as i128).sum();
+        let lo: i128 = lo.iter().map(|&x| x as i128).sum();
+        (hi << 32) + lo + tail
+    }
+
+    // スカラー実装と同じ値になるよう積と和は融合しない
+    #[inline(always)]
+    pub fn clamp_affine(values: &mut [f32], slope: f32, offset: f32, lo: f32, hi: f32) {
+        let (vs, vo) = (f32x8::splat(slope), f32x8::splat(offset));
+        let (vlo, vhi) = (f32x8::splat(lo), f32x8::splat(hi));
+        let mut chunks = values.chunks_exact_mut(LANES);
+        for chunk in &mut chunks {
+            let v = f32x8::from(<[f32; LANES]>::try_from(&*chunk).unwrap());
+            chunk.copy_from_slice(&(vs * v + vo).max(vlo).min(vhi).to_array());
+        }
+        for x in chunks.into_remainder() {
+            *x = (slope * *x + offset).max(lo).min(hi);
+        }
+    }
+}
+
+// AVX2+FMAを有効にしてカーネルを再コンパイルした版
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod avx2 {
+    use super::kernels;
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        kernels::dot(a, b)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot_fixed(a: &[i32], b: &[i32]) -> i128 {
+        kernels::dot_fixed(a, b)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn clamp_affine(values: &mut [f32], slope: f32, offset: f32, lo: f32, hi: f32) {
+        kernels::clamp_affine(values, slope, offset, lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // このCPUで実行できる実装
+    fn levels() -> Vec<SimdLevel> {
+        let mut levels = vec![SimdLevel::Scalar, SimdLevel::Wide];
+        if simd_level() == SimdLevel::Avx2 {
+            levels.push(SimdLevel::Avx2);
+        }
+        levels
+    }
+
+    #[test]
+    fn test_kernels_match_scalar() {
+        for len in [0usize, 5, 8, 16, 37] {
+            let a: Vec<f32> = (0..len).map(|i| (i as f32 * 0.37).sin()).collect();
+            let b: Vec<f32> = (0..len).map(|i| (i as f32 * 0.11).cos()).collect();
+            let scalar = dot_with(SimdLevel::Scalar, &a, &b);
+
+            // 固定小数点は極端な値でも誤差なく一致する
+            let fa: Vec<i32> = (0..len).map(|i| if i % 3 == 0 { i32::MIN } else { i as i32 * 7919 - 40000 }).collect();
+            let fb: Vec<i32> = (0..len).map(|i| if i % 2 == 0 { i32::MIN } else { i32::MAX - i as i32 }).collect();
+            let fixed = dot_fixed_with(SimdLevel::Scalar, &fa, &fb);
+
+            let values: Vec<f32> = (0..len).map(|i| i as f32 * 0.5 - 6.0).collect();
+            let mut expected = values.clone();
+            clamp_affine_with(SimdLevel::Scalar, &mut expected, 0.2, 0.5, 0.0, 1.0);
+
+            for level in levels() {
+                assert!((dot_with(level, &a, &b) - scalar).abs() < 1e-4, "{:?} {}", level, len);
+                assert_eq!(dot_fixed_with(level, &fa, &fb), fixed, "{:?} {}", level, len);
+                let mut clamped = values.clone();
+                clamp_affine_with(level, &mut clamped, 0.2, 0.5, 0.0, 1.0);
+                assert_eq!(clamped, expected, "{:?} {}", level, len);
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use rand::Rng;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,10 @@ pub enum FpgaError {
     TypeConversion(String),
     #[error("計算エラー: {0}")]
     Computation(String),
+    #[error("次元エラー: {0}")]
+    Dimension(String),
+    #[error("フォーマット不一致: Q{}.{} と Q{}.{}", .0.q, .0.int, .1.q, .1.int)]
+    FormatMismatch(QFormat, QFormat),
     #[error("メモリエラー: {0}")]
     Memory(String),
     #[error("設定エラー: {0}")]
@@ -15,7 +20,7 @@ pub enum FpgaError {
 pub type Result<T> = std::result::Result<T, FpgaError>;
 
 // 固定小数点フォーマットの設定
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct QFormat {
     pub q: u8,      // 小数部ビット数
     pub int: u8,    // 整数部ビット数
@@ -49,12 +54,57 @@ impl QFormat {
         scaled as i32
     }
 
+    // 丸めモードを指定したf32からの変換（範囲外は飽和）
+    pub fn from_f32_rounded(&self, value: f32, mode: RoundingMode) -> i32 {
+        self.from_f32_rounded_with(value, mode, &mut rand::thread_rng())
+    }
+
+    // 確率的丸めの乱数源を指定した変換
+    pub fn from_f32_rounded_with(&self, value: f32, mode: RoundingMode, rng: &mut impl Rng) -> i32 {
+        let scaled = value * (1 << self.q) as f32;
+        mode.round_with(scaled, rng) as i32
+    }
+
     // i32からf32への変換
     pub fn to_f32(&self, value: i32) -> f32 {
         value as f32 / (1 << self.q) as f32
     }
 }
 
+// 量子化時の丸めモード
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoundingMode {
+    // 最近接丸め
+    #[default]
+    Nearest,
+    // 確率的丸め（端数を確率として切り上げ、期待値が元の値と一致）
+    Stochastic,
+    // ゼロ方向への切り捨て
+    TowardZero,
+}
+
+impl RoundingMode {
+    pub fn round(self, value: f32) -> f32 {
+        self.round_with(value, &mut rand::thread_rng())
+    }
+
+    // 確率的丸めの乱数源を指定した丸め（再現性が必要なテスト等で使う）
+    pub fn round_with(self, value: f32, rng: &mut impl Rng) -> f32 {
+        match self {
+            RoundingMode::Nearest => value.round(),
+            RoundingMode::TowardZero => value.trunc(),
+            RoundingMode::Stochastic => {
+                let floor = value.floor();
+                if rng.gen::<f32>() < value - floor {
+                    floor + 1.0
+                } else {
+                    floor
+                }
+            }
+        }
+    }
+}
+
 // 三値型
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TrinaryValue {
@@ -72,6 +122,37 @@ impl TrinaryValue {
         }
     }
 
+    // f32からの三値化（丸めた結果を-1..=1に制限）
+    pub fn from_f32(value: f32, mode: RoundingMode) -> Self {
+        let rounded = mode.round(value.clamp(-1.0, 1.0));
+        if rounded > 0.0 {
+            TrinaryValue::Plus
+        } else if rounded < 0.0 {
+            TrinaryValue::Minus
+        } else {
+            TrinaryValue::Zero
+        }
+    }
+
+    // 閾値付き三値化（|x| <= δ はゼロ、それ以外は符号）
+    pub fn from_f32_with_threshold(value: f32, threshold: f32) -> Self {
+        if value > threshold {
+            TrinaryValue::Plus
+        } else if value < -threshold {
+            TrinaryValue::Minus
+        } else {
+            TrinaryValue::Zero
+        }
+    }
+
+    pub fn as_f32(self) -> f32 {
+        match self {
+            TrinaryValue::Zero => 0.0,
+            TrinaryValue::Plus => 1.0,
+            TrinaryValue::Minus => -1.0,
+        }
+    }
+
     pub fn from_i32(value: i32) -> Result<Self> {
         match value & 0b11 {
             0b00 => Ok(TrinaryValue::Zero),
@@ -82,28 +163,478 @@ impl TrinaryValue {
     }
 }
 
-// 固定小数点値
-#[derive(Debug, Clone)]
-pub struct FpgaValue {
-    pub value: i32,
-    pub format: QFormat,
+// 三値化の閾値設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrinaryThreshold {
+    // 固定閾値δ
+    Fixed(f32),
+    // 絶対値のパーセンタイル（0.0〜100.0）からデータごとにδを決定
+    Percentile(f32),
+}
+
+impl TrinaryThreshold {
+    // データに対する閾値δを求める
+    pub fn calibrate(self, values: &[f32]) -> Result<f32> {
+        match self {
+            TrinaryThreshold::Fixed(delta) => {
+                if delta < 0.0 || !delta.is_finite() {
+                    return Err(FpgaError::Configuration(
+                        format!("三値化閾値は0以上の有限値である必要があります: {}", delta)
+                    ));
+                }
+                Ok(delta)
+            }
+            TrinaryThreshold::Percentile(percentile) => {
+                if !(0.0..=100.0).contains(&percentile) {
+                    return Err(FpgaError::Configuration(
+                        format!("パーセンタイルは0から100の間である必要があります: {}", percentile)
+                    ));
+                }
+                if values.is_empty() {
+                    return Err(FpgaError::TypeConversion("空のデータは較正できません".into()));
+                }
+                let mut magnitudes: Vec<f32> = values.iter().map(|x| x.abs()).collect();
+                magnitudes.sort_by(|a, b| a.total_cmp(b));
+                let rank = (percentile / 100.0 * (magnitudes.len() - 1) as f32).round() as usize;
+                Ok(magnitudes[rank])
+            }
+        }
+    }
+}
+
+// 閾値を較正した上でデータ全体を三値化
+pub fn trinarize(values: &[f32], threshold: TrinaryThreshold) -> Result<Vec<TrinaryValue>> {
+    let delta = threshold.calibrate(values)?;
+    Ok(values.iter()
+        .map(|&x| TrinaryValue::from_f32_with_threshold(x, delta))
+        .collect())
+}
+
+// 演算値（データ形式ごとの表現）
+#[derive(Debug, Clone, PartialEq)]
+pub enum FpgaValue {
+    // 完全精度（32ビット浮動小数点）
+    Float(f32),
+    // 固定小数点（生の値とフォーマット）
+    Fixed { value: i32, format: QFormat },
+    // 三値（-1, 0, 1）
+    Trinary(TrinaryValue),
 }
 
 impl FpgaValue {
-    // f32からの生成
+    // f32から固定小数点値を生成
     pub fn from_f32(value: f32, format: QFormat) -> Self {
-        Self {
+        FpgaValue::Fixed {
             value: format.from_f32(value),
             format,
         }
     }
 
+    // 丸めモードを指定したf32からの生成
+    pub fn from_f32_rounded(value: f32, format: QFormat, mode: RoundingMode) -> Self {
+        FpgaValue::Fixed {
+            value: format.from_f32_rounded(value, mode),
+            format,
+        }
+    }
+
     // f32への変換
     pub fn as_f32(&self) -> f32 {
-        self.format.to_f32(self.value)
+        match *self {
+            FpgaValue::Float(x) => x,
+            FpgaValue::Fixed { value, format } => format.to_f32(value),
+            FpgaValue::Trinary(t) => t.as_f32(),
+        }
+    }
+
+    // 固定小数点値の生の値（それ以外はNone）
+    pub fn raw(&self) -> Option<i32> {
+        match *self {
+            FpgaValue::Fixed { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    // 固定小数点値のフォーマット（それ以外はNone）
+    pub fn format(&self) -> Option<QFormat> {
+        match *self {
+            FpgaValue::Fixed { format, .. } => Some(format),
+            _ => None,
+        }
+    }
+
+    // 同じ形式の値で表した数値（固定小数点はフォーマットを引き継ぐ）
+    pub fn with_value(&self, value: f32) -> Self {
+        match *self {
+            FpgaValue::Fixed { format, .. } => FpgaValue::from_f32_rounded(value, format, RoundingMode::Nearest),
+            _ => FpgaValue::Float(value),
+        }
+    }
+
+    // with_valueの飽和検出版（固定小数点の範囲外は飽和させ、戻り値の真偽値は飽和の有無）
+    pub fn with_value_saturating(&self, value: f32) -> (Self, bool) {
+        match *self {
+            FpgaValue::Fixed { format, .. } => {
+                let scaled = (value as f64 * (1u64 << format.q) as f64).round();
+                Self::from_wide(scaled as i64, format)
+            }
+            _ => (FpgaValue::Float(value), false),
+        }
+    }
+
+    // 二項演算のオペランドの組み合わせ
+    //
+    // 固定小数点同士は同一フォーマットの生の値、浮動小数点同士はf32の組、
+    // 三値同士は三値の組を返す。異なるフォーマット・形式の値は尺度が異なるためエラー。
+    fn operands(&self, other: &FpgaValue) -> Result<Operands> {
+        match (self, other) {
+            (FpgaValue::Fixed { value: a, format: fa }, FpgaValue::Fixed { value: b, format: fb }) => {
+                if fa != fb {
+                    return Err(FpgaError::FormatMismatch(*fa, *fb));
+                }
+                Ok(Operands::Fixed(*a, *b, *fa))
+            }
+            (FpgaValue::Float(a), FpgaValue::Float(b)) => Ok(Operands::Float(*a, *b)),
+            (FpgaValue::Trinary(a), FpgaValue::Trinary(b)) => Ok(Operands::Trinary(*a, *b)),
+            _ => Err(FpgaError::TypeConversion(
+                format!("異なるデータ形式の値は演算できません: {:?} と {:?}", self, other)
+            )),
+        }
+    }
+
+    // 飽和加算（オーバーフロー時はi32の最大・最小値に張り付く）
+    //
+    // 三値同士の和は-2..=2になるためf32で返す。
+    pub fn saturating_add(&self, other: &FpgaValue) -> Result<Self> {
+        Ok(match self.operands(other)? {
+            Operands::Fixed(a, b, format) => FpgaValue::Fixed { value: a.saturating_add(b), format },
+            Operands::Float(a, b) => FpgaValue::Float(a + b),
+            Operands::Trinary(a, b) => FpgaValue::Float(a.as_f32() + b.as_f32()),
+        })
+    }
+
+    // オーバーフロー検査付き加算
+    pub fn checked_add(&self, other: &FpgaValue) -> Result<Self> {
+        match self.operands(other)? {
+            Operands::Fixed(a, b, format) => {
+                let value = a.checked_add(b).ok_or_else(|| {
+                    FpgaError::Computation(format!(
+                        "固定小数点加算でオーバーフローが発生しました: {} + {}",
+                        self.as_f32(), other.as_f32()
+                    ))
+                })?;
+                Ok(FpgaValue::Fixed { value, format })
+            }
+            Operands::Float(a, b) => Ok(FpgaValue::Float(a + b)),
+            Operands::Trinary(a, b) => Ok(FpgaValue::Float(a.as_f32() + b.as_f32())),
+        }
+    }
+
+    // 飽和乗算（i64で積を計算し小数部ビット数分シフトしてから丸め込む）
+    //
+    // 三値同士の積は三値のまま。
+    pub fn saturating_mul(&self, other: &FpgaValue) -> Result<Self> {
+        Ok(match self.operands(other)? {
+            Operands::Fixed(a, b, format) => {
+                let product = (a as i64 * b as i64) >> format.q;
+                FpgaValue::Fixed { value: clamp_i64(product), format }
+            }
+            Operands::Float(a, b) => FpgaValue::Float(a * b),
+            Operands::Trinary(a, b) => FpgaValue::Trinary(
+                TrinaryValue::from_f32_with_threshold(a.as_f32() * b.as_f32(), 0.0)
+            ),
+        })
+    }
+
+    // i64で累積し最後に一度だけ飽和させる（戻り値の真偽値は飽和の有無）
+    pub fn accumulate<'a>(
+        values: impl IntoIterator<Item = &'a FpgaValue>,
+        format: QFormat,
+    ) -> Result<(Self, bool)> {
+        let mut sum: i64 = 0;
+        for v in values {
+            match *v {
+                FpgaValue::Fixed { value, format: f } if f == format => sum += value as i64,
+                FpgaValue::Fixed { format: f, .. } => return Err(FpgaError::FormatMismatch(format, f)),
+                _ => return Err(FpgaError::TypeConversion(
+                    format!("固定小数点以外の値は累積できません: {:?}", v)
+                )),
+            }
+        }
+        Ok(Self::from_wide(sum, format))
+    }
+
+    // i64の累積値を出力フォーマットへ飽和させる（戻り値の真偽値は飽和の有無）
+    pub fn from_wide(sum: i64, format: QFormat) -> (Self, bool) {
+        let value = clamp_i64(sum);
+        (FpgaValue::Fixed { value, format }, value as i64 != sum)
+    }
+}
+
+enum Operands {
+    Fixed(i32, i32, QFormat),
+    Float(f32, f32),
+    Trinary(TrinaryValue, TrinaryValue),
+}
+
+/// 行列・ベクトルのデータ形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    // 完全精度（32ビット浮動小数点）
+    Full,
+    // 固定小数点
+    Fixed(QFormat),
+    // 三値化（-1, 0, 1）。閾値を指定しない場合は丸めモードに従って三値化する
+    Trinary(Option<TrinaryThreshold>),
+}
+
+/// f32からデータ形式に応じたFpgaValueへの変換
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataConverter {
+    format: DataFormat,
+    rounding: RoundingMode,
+}
+
+impl DataConverter {
+    pub fn new(format: DataFormat) -> Self {
+        Self { format, rounding: RoundingMode::default() }
+    }
+
+    // 量子化時の丸めモードを指定
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn format(&self) -> DataFormat {
+        self.format
+    }
+
+    // 1要素の変換（パーセンタイル閾値はデータ全体が必要なためconvert_allを使う）
+    pub fn convert(&self, value: f32) -> Result<FpgaValue> {
+        if !value.is_finite() {
+            return Err(FpgaError::TypeConversion(format!("有限でない値は変換できません: {}", value)));
+        }
+        Ok(match self.format {
+            DataFormat::Full => FpgaValue::Float(value),
+            DataFormat::Fixed(format) => FpgaValue::from_f32_rounded(value, format, self.rounding),
+            DataFormat::Trinary(None) => FpgaValue::Trinary(TrinaryValue::from_f32(value, self.rounding)),
+            DataFormat::Trinary(Some(threshold @ TrinaryThreshold::Fixed(_))) => {
+                FpgaValue::Trinary(TrinaryValue::from_f32_with_threshold(value, threshold.calibrate(&[])?))
+            }
+            DataFormat::Trinary(Some(TrinaryThreshold::Percentile(_))) => {
+                return Err(FpgaError::Configuration(
+                    "パーセンタイル閾値の三値化はデータ全体で変換する必要があります".into()
+                ));
+            }
+        })
+    }
+
+    // データ全体の変換（三値化の閾値はデータ全体で較正する）
+    pub fn convert_all(&self, values: &[f32]) -> Result<Vec<FpgaValue>> {
+        match self.format {
+            DataFormat::Trinary(Some(threshold)) => {
+                if let Some(&value) = values.iter().find(|x| !x.is_finite()) {
+                    return Err(FpgaError::TypeConversion(format!("有限でない値は変換できません: {}", value)));
+                }
+                Ok(trinarize(values, threshold)?.into_iter().map(FpgaValue::Trinary).collect())
+            }
+            _ => values.iter().map(|&x| self.convert(x)).collect(),
+        }
     }
 }
 
+fn clamp_i64(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
 // 行列の次元定数
 pub const MATRIX_SIZE: usize = 16;
-pub const VECTOR_SIZE: usize = 16;
\ No newline at end of file
+pub const VECTOR_SIZE: usize = 16;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn q23_8() -> QFormat {
+        QFormat::new(23, 8).unwrap()
+    }
+
+    #[test]
+    fn test_saturating_arithmetic() {
+        let format = q23_8();
+        let max = FpgaValue::Fixed { value: i32::MAX, format };
+        let one = FpgaValue::from_f32(1.0, format);
+
+        assert_eq!(max.saturating_add(&one).unwrap().raw(), Some(i32::MAX));
+        assert!(max.checked_add(&one).is_err());
+        assert_eq!(one.checked_add(&one).unwrap().as_f32(), 2.0);
+
+        let big = FpgaValue::from_f32(200.0, format);
+        assert_eq!(big.saturating_mul(&big).unwrap().raw(), Some(i32::MAX));
+        assert_eq!(one.saturating_mul(&big).unwrap().as_f32(), 200.0);
+    }
+
+    #[test]
+    fn test_wide_accumulation() {
+        let format = q23_8();
+        let values = vec![FpgaValue::from_f32(200.0, format); 2];
+        let (sum, saturated) = FpgaValue::accumulate(&values, format).unwrap();
+        assert!(saturated);
+        assert_eq!(sum.raw(), Some(i32::MAX));
+
+        let values = vec![
+            FpgaValue::from_f32(150.0, format),
+            FpgaValue::from_f32(150.0, format),
+            FpgaValue::from_f32(-200.0, format),
+        ];
+        let (sum, saturated) = FpgaValue::accumulate(&values, format).unwrap();
+        assert!(!saturated);
+        assert_eq!(sum.as_f32(), 100.0);
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let format = q23_8();
+        let lsb = 1.0 / (1 << 23) as f32;
+        let value = 2.75 * lsb;
+
+        assert_eq!(format.from_f32_rounded(value, RoundingMode::Nearest), 3);
+        assert_eq!(format.from_f32_rounded(value, RoundingMode::TowardZero), 2);
+        assert_eq!(format.from_f32_rounded(-value, RoundingMode::TowardZero), -2);
+
+        let stochastic = format.from_f32_rounded(value, RoundingMode::Stochastic);
+        assert!(stochastic == 2 || stochastic == 3);
+
+        // 確率的丸めの期待値は元の値に近づく（乱数は固定シードで再現可能にする）
+        let mut rng = StdRng::seed_from_u64(2841);
+        let mean = (0..10_000)
+            .map(|_| format.from_f32_rounded_with(value, RoundingMode::Stochastic, &mut rng) as f64)
+            .sum::<f64>() / 10_000.0;
+        assert!((mean - 2.75).abs() < 0.05);
+
+        // 同じシードからは同じ丸め結果の列が得られる
+        let sequence = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..32).map(|_| RoundingMode::Stochastic.round_with(0.5, &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(7), sequence(7));
+    }
+
+    #[test]
+    fn test_trinary_rounding() {
+        assert_eq!(TrinaryValue::from_f32(0.4, RoundingMode::Nearest), TrinaryValue::Zero);
+        assert_eq!(TrinaryValue::from_f32(0.6, RoundingMode::Nearest), TrinaryValue::Plus);
+        assert_eq!(TrinaryValue::from_f32(-3.0, RoundingMode::Nearest), TrinaryValue::Minus);
+        assert_eq!(TrinaryValue::from_f32(0.9, RoundingMode::TowardZero), TrinaryValue::Zero);
+        assert_eq!(TrinaryValue::from_f32(1.0, RoundingMode::Stochastic), TrinaryValue::Plus);
+    }
+
+    #[test]
+    fn test_threshold_trinarization() {
+        let values = [0.05, -0.05, 0.5, -0.5, 0.0];
+        let result = trinarize(&values, TrinaryThreshold::Fixed(0.1)).unwrap();
+        assert_eq!(result, vec![
+            TrinaryValue::Zero,
+            TrinaryValue::Zero,
+            TrinaryValue::Plus,
+            TrinaryValue::Minus,
+            TrinaryValue::Zero,
+        ]);
+
+        // 中央値の絶対値を閾値とする較正
+        let values = [0.1, -0.2, 0.3, -0.4, 0.5];
+        let delta = TrinaryThreshold::Percentile(50.0).calibrate(&values).unwrap();
+        assert_eq!(delta, 0.3);
+
+        assert!(TrinaryThreshold::Fixed(-1.0).calibrate(&values).is_err());
+        assert!(TrinaryThreshold::Percentile(150.0).calibrate(&values).is_err());
+
+        // 変換設定の閾値はデータ全体の変換で較正され、行列・ベクトルの生成にも使われる
+        let converter = DataConverter::new(DataFormat::Trinary(Some(TrinaryThreshold::Percentile(50.0))));
+        let expected: Vec<FpgaValue> = [0.0, 0.0, 0.0, -1.0, 1.0].iter()
+            .map(|&x| FpgaValue::Trinary(TrinaryValue::from_f32(x, RoundingMode::Nearest)))
+            .collect();
+        assert_eq!(converter.convert_all(&values).unwrap(), expected);
+        assert_eq!(crate::math::Vector::from_f32(&values, &converter).unwrap().data(), expected.as_slice());
+        let matrix = crate::math::Matrix::from_f32(&[values[..3].to_vec(), values[2..].to_vec()], &converter).unwrap();
+        assert_eq!(matrix.data()[1], expected[2..].to_vec());
+        assert!(converter.convert(0.5).is_err());
+
+        let converter = DataConverter::new(DataFormat::Trinary(Some(TrinaryThreshold::Fixed(0.25))));
+        assert_eq!(converter.convert(0.2).unwrap(), FpgaValue::Trinary(TrinaryValue::Zero));
+        assert_eq!(converter.convert(-0.3).unwrap(), FpgaValue::Trinary(TrinaryValue::Minus));
+    }
+
+    #[test]
+    fn test_format_pairings() {
+        // 有効な全フォーマットの組み合わせで、一致時のみ演算が成功する
+        let formats: Vec<QFormat> = (19..=29)
+            .map(|q| QFormat::new(q, 31 - q).unwrap())
+            .collect();
+
+        for &a in &formats {
+            for &b in &formats {
+                let x = FpgaValue::from_f32(1.0, a);
+                let y = FpgaValue::from_f32(1.0, b);
+                let results = [
+                    x.saturating_add(&y).err(),
+                    x.checked_add(&y).err(),
+                    x.saturating_mul(&y).err(),
+                    FpgaValue::accumulate([&x, &y], a).err(),
+                ];
+                for result in results {
+                    match result {
+                        None => assert_eq!(a, b),
+                        Some(FpgaError::FormatMismatch(l, r)) => {
+                            assert_ne!(a, b);
+                            assert_eq!((l, r), (a, b));
+                        }
+                        Some(e) => panic!("unexpected error: {}", e),
+                    }
+                }
+            }
+        }
+
+        // 形式の組み合わせ：同じ形式同士のみ演算でき、それ以外は型変換エラー
+        let format = QFormat::new(23, 8).unwrap();
+        let values = [
+            FpgaValue::Float(0.5),
+            FpgaValue::from_f32(0.5, format),
+            FpgaValue::Trinary(TrinaryValue::Plus),
+            FpgaValue::Trinary(TrinaryValue::Minus),
+            FpgaValue::Trinary(TrinaryValue::Zero),
+        ];
+        let kind = |v: &FpgaValue| std::mem::discriminant(v);
+        for x in &values {
+            for y in &values {
+                let results = [x.saturating_add(y), x.checked_add(y), x.saturating_mul(y)];
+                if kind(x) != kind(y) {
+                    for result in results {
+                        assert!(matches!(result, Err(FpgaError::TypeConversion(_))), "{:?} {:?}", x, y);
+                    }
+                } else {
+                    let [sum, checked, product] = results.map(Result::unwrap);
+                    assert_eq!(sum, checked);
+                    assert_eq!(sum.as_f32(), x.as_f32() + y.as_f32());
+                    assert_eq!(product.as_f32(), x.as_f32() * y.as_f32());
+                    // 三値同士の和はf32、積は三値
+                    if let FpgaValue::Trinary(_) = x {
+                        assert!(matches!(sum, FpgaValue::Float(_)));
+                        assert!(matches!(product, FpgaValue::Trinary(_)));
+                    }
+                }
+
+                let accumulated = FpgaValue::accumulate([x, y], format);
+                match (x, y) {
+                    (FpgaValue::Fixed { .. }, FpgaValue::Fixed { .. }) => {
+                        assert_eq!(accumulated.unwrap().0.as_f32(), 1.0);
+                    }
+                    _ => assert!(matches!(accumulated, Err(FpgaError::TypeConversion(_)))),
+                }
+            }
+        }
+    }
+}